@@ -26,6 +26,22 @@ fn delta(c: &mut Criterion) {
     let mut packed = [0; 128 * W / size_of::<u16>()];
     BitPacking::pack::<W>(&deltas, &mut packed);
 
+    group.bench_function("delta u16 fused encode", |b| {
+        b.iter(|| {
+            let mut packed = [0; 128 * W / size_of::<u16>()];
+            Delta::delta_pack::<W>(&transposed, &[0; 64], &mut packed);
+        });
+    });
+
+    group.bench_function("delta u16 unfused encode", |b| {
+        b.iter(|| {
+            let mut deltas = [0; 1024];
+            Delta::delta(&transposed, &[0; 64], &mut deltas);
+            let mut packed = [0; 128 * W / size_of::<u16>()];
+            BitPacking::pack::<W>(&deltas, &mut packed);
+        });
+    });
+
     group.bench_function("delta u16 fused", |b| {
         b.iter(|| {
             let mut unpacked = [0; 1024];
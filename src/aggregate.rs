@@ -0,0 +1,246 @@
+use crate::{
+    unpack, BitPackWidth, Delta, FastLanes, FoR, Mask1024, SupportedBitPackWidth, MASK_ROWS,
+};
+use paste::paste;
+
+/// The aggregates of the selected elements of a vector. `min` and `max` are `None` if no element
+/// is selected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Aggregates<T, S> {
+    pub count: usize,
+    pub sum: S,
+    pub min: Option<T>,
+    pub max: Option<T>,
+}
+
+/// Aggregation directly on packed vectors, without materializing the decoded values.
+///
+/// Every kernel optionally takes a selection mask as written by `Compare`, and otherwise
+/// aggregates all 1024 elements. Sums are accumulated in `Self::Sum`, which is wide enough to
+/// never overflow for a single vector.
+pub trait Aggregate: Delta + FoR {
+    type Sum;
+
+    fn aggregate<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        selection: Option<&Mask1024>,
+    ) -> Aggregates<Self, Self::Sum>
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    fn for_aggregate<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: Self,
+        selection: Option<&Mask1024>,
+    ) -> Aggregates<Self, Self::Sum>
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    fn delta_aggregate<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        base: &[Self; Self::LANES],
+        selection: Option<&Mask1024>,
+    ) -> Aggregates<Self, Self::Sum>
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+}
+
+macro_rules! impl_aggregate {
+    ($T:ty, $Sum:ty) => {
+        paste! {
+            impl Aggregate for $T {
+                type Sum = $Sum;
+
+                fn aggregate<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    selection: Option<&Mask1024>,
+                ) -> Aggregates<Self, $Sum>
+                where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    let selection = selection.unwrap_or(&Mask1024::ALL);
+                    let mut acc = Accumulator::<$T, $Sum>::new();
+                    for lane in 0..Self::LANES {
+                        unpack!($T, W, input, lane, |$idx, $elem| {
+                            acc.push((selection.0[$idx % 16] >> MASK_ROWS[$idx]) & 1 == 1, $elem);
+                        });
+                    }
+                    acc.finish()
+                }
+
+                fn for_aggregate<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    reference: Self,
+                    selection: Option<&Mask1024>,
+                ) -> Aggregates<Self, $Sum>
+                where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    // Aggregate the offsets, then translate the results by `reference` once.
+                    let offsets = Self::aggregate::<W>(input, selection);
+                    Aggregates {
+                        count: offsets.count,
+                        sum: offsets.sum + offsets.count as $Sum * <$Sum>::from(reference),
+                        min: offsets.min.map(|min| min.wrapping_add(reference)),
+                        max: offsets.max.map(|max| max.wrapping_add(reference)),
+                    }
+                }
+
+                fn delta_aggregate<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    base: &[Self; Self::LANES],
+                    selection: Option<&Mask1024>,
+                ) -> Aggregates<Self, $Sum>
+                where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    let selection = selection.unwrap_or(&Mask1024::ALL);
+                    let mut acc = Accumulator::<$T, $Sum>::new();
+                    #[allow(unused_assignments)]
+                    for lane in 0..Self::LANES {
+                        let mut prev = base[lane];
+                        unpack!($T, W, input, lane, |$idx, $elem| {
+                            let next = $elem.wrapping_add(prev);
+                            acc.push((selection.0[$idx % 16] >> MASK_ROWS[$idx]) & 1 == 1, next);
+                            prev = next;
+                        });
+                    }
+                    acc.finish()
+                }
+            }
+
+            impl Accumulator<$T, $Sum> {
+                #[inline]
+                fn new() -> Self {
+                    Self { count: 0, sum: 0, min: <$T>::MAX, max: <$T>::MIN }
+                }
+
+                /// Branch-free: unselected elements add nothing and are neutral for min and max.
+                #[inline(always)]
+                fn push(&mut self, selected: bool, value: $T) {
+                    self.count += usize::from(selected);
+                    self.sum += <$Sum>::from(value) * <$Sum>::from(selected);
+                    self.min = self.min.min(if selected { value } else { <$T>::MAX });
+                    self.max = self.max.max(if selected { value } else { <$T>::MIN });
+                }
+
+                #[inline]
+                fn finish(self) -> Aggregates<$T, $Sum> {
+                    let any = self.count > 0;
+                    Aggregates {
+                        count: self.count,
+                        sum: self.sum,
+                        min: any.then_some(self.min),
+                        max: any.then_some(self.max),
+                    }
+                }
+            }
+        }
+    };
+}
+
+struct Accumulator<T, S> {
+    count: usize,
+    sum: S,
+    min: T,
+    max: T,
+}
+
+impl_aggregate!(u8, u32);
+impl_aggregate!(u16, u32);
+impl_aggregate!(u32, u64);
+impl_aggregate!(u64, u128);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BitPacking, Transpose};
+    use core::mem::size_of;
+
+    fn mask(selected: &[bool; 1024]) -> Mask1024 {
+        Mask1024::from(selected)
+    }
+
+    fn reference<T: Aggregate<Sum = S>, S: From<T> + core::iter::Sum>(
+        values: &[T; 1024],
+        selected: &[bool; 1024],
+    ) -> Aggregates<T, S> {
+        let selected = || {
+            values
+                .iter()
+                .zip(selected)
+                .filter(|(_, &s)| s)
+                .map(|(&v, _)| v)
+        };
+        Aggregates {
+            count: selected().count(),
+            sum: selected().map(S::from).sum(),
+            min: selected().min(),
+            max: selected().max(),
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        const W: usize = 8;
+        let values: [u8; 1024] = core::array::from_fn(|i| (i * 37 % 256) as u8);
+        let mut packed = [0; 128 * W / size_of::<u8>()];
+        BitPacking::pack::<W>(&values, &mut packed);
+
+        let all = [true; 1024];
+        let result = Aggregate::aggregate::<W>(&packed, None);
+        assert_eq!(result, reference(&values, &all));
+        assert_eq!(result.sum, 1024 * 255 / 2);
+
+        let odd: [bool; 1024] = core::array::from_fn(|i| i % 3 == 1);
+        assert_eq!(
+            Aggregate::aggregate::<W>(&packed, Some(&mask(&odd))),
+            reference(&values, &odd)
+        );
+
+        let none = [false; 1024];
+        let result = Aggregate::aggregate::<W>(&packed, Some(&mask(&none)));
+        assert_eq!(result, reference(&values, &none));
+        assert_eq!((result.min, result.max), (None, None));
+    }
+
+    #[test]
+    fn test_for_aggregate() {
+        const W: usize = 12;
+        let values: [u32; 1024] = core::array::from_fn(|i| u32::MAX - 4000 + (i * 7 % 4000) as u32);
+        let mut packed = [0; 128 * W / size_of::<u32>()];
+        FoR::for_pack::<W>(&values, u32::MAX - 4000, &mut packed);
+
+        let selected: [bool; 1024] = core::array::from_fn(|i| i < 700);
+        assert_eq!(
+            Aggregate::for_aggregate::<W>(&packed, u32::MAX - 4000, None),
+            reference(&values, &[true; 1024])
+        );
+        assert_eq!(
+            Aggregate::for_aggregate::<W>(&packed, u32::MAX - 4000, Some(&mask(&selected))),
+            reference(&values, &selected)
+        );
+    }
+
+    #[test]
+    fn test_delta_aggregate() {
+        const W: usize = 5;
+        let values: [u64; 1024] = core::array::from_fn(|i| (1 << 40) + (i * i) as u64 / 100);
+        let mut transposed = [0; 1024];
+        Transpose::transpose(&values, &mut transposed);
+        let base = *arrayref::array_ref![transposed, 0, 16];
+        let mut packed = [0; 128 * W / size_of::<u64>()];
+        Delta::delta_pack::<W>(&transposed, &base, &mut packed);
+
+        // The mask addresses elements in the transposed order they are packed in.
+        let selected: [bool; 1024] = core::array::from_fn(|i| transposed[i] % 2 == 0);
+        assert_eq!(
+            Aggregate::delta_aggregate::<W>(&packed, &base, Some(&mask(&selected))),
+            reference(&transposed, &selected)
+        );
+        assert_eq!(
+            Aggregate::delta_aggregate::<W>(&packed, &base, None),
+            reference(&values, &[true; 1024])
+        );
+    }
+}
@@ -0,0 +1,363 @@
+#![allow(
+    clippy::cast_possible_wrap,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+
+use arrayref::{array_mut_ref, array_ref};
+use core::mem::size_of;
+
+use crate::util::{compact_with, sample_index, SAMPLES};
+use crate::{seq_t, unpack, BitPackWidth, FastLanes, FoR, SupportedBitPackWidth};
+use paste::paste;
+
+/// A per-vector exponent/factor pair. Values are encoded as `round(v * 10^e * 10^-f)` and
+/// decoded as `n * 10^f * 10^-e`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Exponents {
+    pub e: u8,
+    pub f: u8,
+}
+
+/// Adaptive Lossless floating-Point (ALP) encoding of `Self::Float` vectors into `Self` integers.
+///
+/// Encoded integers have their sign bit flipped, so that unsigned order matches signed order and
+/// the output can be analyzed and packed with `FoR` directly. Values that do not round-trip are
+/// recorded as (index, value) exceptions and replaced by the first value that does, leaving the
+/// packed width unaffected.
+pub trait Alp: FoR {
+    type Float: Copy;
+
+    /// The largest exponent `e` whose powers of ten fit into `Self`.
+    const MAX_EXPONENT: u8;
+
+    /// Picks the exponents that minimize the estimated encoded size of a sample of `input`.
+    fn find_exponents(input: &[Self::Float; 1024]) -> Exponents;
+
+    /// Returns the number of exceptions written.
+    fn alp_encode(
+        input: &[Self::Float; 1024],
+        exponents: Exponents,
+        output: &mut [Self; 1024],
+        exception_indices: &mut [u16; 1024],
+        exception_values: &mut [Self::Float; 1024],
+    ) -> usize;
+
+    /// Decodes integers written by `alp_encode`, without applying exceptions.
+    fn alp_decode(input: &[Self; 1024], exponents: Exponents, output: &mut [Self::Float; 1024]);
+
+    /// Decodes `FoR` packed integers written by `alp_encode`, applying the exceptions.
+    fn unalp_pack<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: Self,
+        exponents: Exponents,
+        exception_indices: &[u16],
+        exception_values: &[Self::Float],
+        output: &mut [Self::Float; 1024],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Decodes `FoR` packed integers of a runtime-known width `W`, see `unalp_pack`.
+    ///
+    /// # Safety
+    /// The input slice must be of length `1024 * W / T`, where `T` is the bit-width of Self and
+    /// `W` is the packed width. The output slice must be of exactly length 1024.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_unalp_pack(
+        width: usize,
+        input: &[Self],
+        reference: Self,
+        exponents: Exponents,
+        exception_indices: &[u16],
+        exception_values: &[Self::Float],
+        output: &mut [Self::Float],
+    );
+}
+
+const F10_F32: [f32; 11] = [1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10];
+const IF10_F32: [f32; 11] = [
+    1e0, 1e-1, 1e-2, 1e-3, 1e-4, 1e-5, 1e-6, 1e-7, 1e-8, 1e-9, 1e-10,
+];
+const F10_F64: [f64; 19] = [
+    1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+    1e17, 1e18,
+];
+const IF10_F64: [f64; 19] = [
+    1e0, 1e-1, 1e-2, 1e-3, 1e-4, 1e-5, 1e-6, 1e-7, 1e-8, 1e-9, 1e-10, 1e-11, 1e-12, 1e-13, 1e-14,
+    1e-15, 1e-16, 1e-17, 1e-18,
+];
+
+macro_rules! impl_alp {
+    ($T:ty, $S:ty, $F:ty, $F10:ident, $IF10:ident, $magic:expr) => {
+        paste! {
+            impl Alp for $T {
+                type Float = $F;
+
+                const MAX_EXPONENT: u8 = ($F10.len() - 1) as u8;
+
+                fn find_exponents(input: &[$F; 1024]) -> Exponents {
+                    let mut best = (Exponents::default(), usize::MAX);
+                    for e in 0..=Self::MAX_EXPONENT {
+                        for f in 0..=e {
+                            let exponents = Exponents { e, f };
+                            let (mut min, mut max, mut exceptions) = (<$T>::MAX, <$T>::MIN, 0);
+                            for i in 0..SAMPLES {
+                                let value = input[sample_index(i)];
+                                let encoded = [<encode_ $F>](value, exponents);
+                                if [<decode_ $F>](encoded, exponents).to_bits() == value.to_bits() {
+                                    min = min.min(encoded);
+                                    max = max.max(encoded);
+                                } else {
+                                    exceptions += 1;
+                                }
+                            }
+                            let width = if min > max {
+                                0
+                            } else {
+                                Self::T - (max - min).leading_zeros() as usize
+                            };
+                            // Exceptions cost their value and a u16 index.
+                            let size = SAMPLES * width + exceptions * (Self::T + 16);
+                            if size < best.1 {
+                                best = (exponents, size);
+                            }
+                        }
+                    }
+                    best.0
+                }
+
+                fn alp_encode(
+                    input: &[$F; 1024],
+                    exponents: Exponents,
+                    output: &mut [Self; 1024],
+                    exception_indices: &mut [u16; 1024],
+                    exception_values: &mut [$F; 1024],
+                ) -> usize {
+                    let n = compact_with(|n, i| {
+                        output[i] = [<encode_ $F>](input[i], exponents);
+                        exception_indices[n] = i as u16;
+                        exception_values[n] = input[i];
+                        [<decode_ $F>](output[i], exponents).to_bits() != input[i].to_bits()
+                    });
+
+                    // Exception indices are ascending, so the first gap is the first encodable value.
+                    let first = (0..n).find(|&i| exception_indices[i] as usize != i).unwrap_or(n);
+                    if first < 1024 {
+                        let fill = output[first];
+                        for &idx in &exception_indices[..n] {
+                            output[idx as usize] = fill;
+                        }
+                    }
+                    n
+                }
+
+                fn alp_decode(input: &[Self; 1024], exponents: Exponents, output: &mut [$F; 1024]) {
+                    for i in 0..1024 {
+                        output[i] = [<decode_ $F>](input[i], exponents);
+                    }
+                }
+
+                fn unalp_pack<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    reference: Self,
+                    exponents: Exponents,
+                    exception_indices: &[u16],
+                    exception_values: &[$F],
+                    output: &mut [$F; 1024],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    debug_assert_eq!(exception_indices.len(), exception_values.len());
+                    let (f10, if10) = ($F10[exponents.f as usize], $IF10[exponents.e as usize]);
+                    for lane in 0..Self::LANES {
+                        unpack!($T, W, input, lane, |$idx, $elem| {
+                            let encoded = ($elem.wrapping_add(reference) ^ (1 << (Self::T - 1))) as $S;
+                            output[$idx] = encoded as $F * f10 * if10
+                        });
+                    }
+                    for (&idx, &value) in exception_indices.iter().zip(exception_values) {
+                        output[idx as usize] = value;
+                    }
+                }
+
+                unsafe fn unchecked_unalp_pack(
+                    width: usize,
+                    input: &[Self],
+                    reference: Self,
+                    exponents: Exponents,
+                    exception_indices: &[u16],
+                    exception_values: &[$F],
+                    output: &mut [$F],
+                ) {
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(input.len(), packed_len, "Input buffer must be of size 1024 * W / T");
+                    debug_assert_eq!(output.len(), 1024, "Output buffer must be of size 1024");
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => Self::unalp_pack::<W>(
+                                array_ref![input, 0, 1024 * W / <$T>::T],
+                                reference,
+                                exponents,
+                                exception_indices,
+                                exception_values,
+                                array_mut_ref![output, 0, 1024],
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => Self::unalp_pack::<{ Self::T }>(
+                                array_ref![input, 0, 1024],
+                                reference,
+                                exponents,
+                                exception_indices,
+                                exception_values,
+                                array_mut_ref![output, 0, 1024],
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+            }
+
+            /// Rounds to the nearest integer by pushing the fraction out of the mantissa. This is
+            /// only exact for small magnitudes, anything else fails to round-trip and becomes an
+            /// exception.
+            #[inline]
+            fn [<encode_ $F>](value: $F, exponents: Exponents) -> $T {
+                let scaled = value * $F10[exponents.e as usize] * $IF10[exponents.f as usize];
+                (((scaled + $magic) - $magic) as $S as $T) ^ (1 << (<$T>::T - 1))
+            }
+
+            #[inline]
+            fn [<decode_ $F>](encoded: $T, exponents: Exponents) -> $F {
+                let encoded = (encoded ^ (1 << (<$T>::T - 1))) as $S;
+                encoded as $F * $F10[exponents.f as usize] * $IF10[exponents.e as usize]
+            }
+        }
+    };
+}
+
+// 2^23 + 2^22
+impl_alp!(u32, i32, f32, F10_F32, IF10_F32, 12_582_912.0);
+// 2^52 + 2^51
+impl_alp!(u64, i64, f64, F10_F64, IF10_F64, 6_755_399_441_055_744.0);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_alp_f64() {
+        const W: usize = 16;
+        let mut values = [0f64; 1024];
+        for i in 0..1024 {
+            values[i] = f64::from(i32::try_from(i).unwrap() * 37 - 20_000) / 100.0;
+        }
+        values[10] = core::f64::consts::PI;
+        values[20] = -0.0;
+        values[30] = f64::NAN;
+
+        let exponents = u64::find_exponents(&values);
+        assert_eq!(exponents.e - exponents.f, 2);
+
+        let mut encoded = [0u64; 1024];
+        let mut exception_indices = [0; 1024];
+        let mut exception_values = [0.0; 1024];
+        let n = Alp::alp_encode(
+            &values,
+            exponents,
+            &mut encoded,
+            &mut exception_indices,
+            &mut exception_values,
+        );
+        assert_eq!(exception_indices[..n], [10, 20, 30]);
+
+        let (reference, width) = FoR::analyze(&encoded);
+        assert_eq!(width, W);
+        let mut packed = [0; 128 * W / size_of::<u64>()];
+        FoR::for_pack::<W>(&encoded, reference, &mut packed);
+
+        let mut decoded = [0.0; 1024];
+        Alp::unalp_pack::<W>(
+            &packed,
+            reference,
+            exponents,
+            &exception_indices[..n],
+            &exception_values[..n],
+            &mut decoded,
+        );
+        assert!(values
+            .iter()
+            .zip(&decoded)
+            .all(|(a, b)| a.to_bits() == b.to_bits()));
+
+        let mut unchecked = [0.0; 1024];
+        unsafe {
+            u64::unchecked_unalp_pack(
+                W,
+                &packed,
+                reference,
+                exponents,
+                &exception_indices[..n],
+                &exception_values[..n],
+                &mut unchecked,
+            );
+        }
+        assert!(values
+            .iter()
+            .zip(&unchecked)
+            .all(|(a, b)| a.to_bits() == b.to_bits()));
+    }
+
+    #[test]
+    fn test_alp_f32() {
+        let mut values = [0f32; 1024];
+        for i in 0..1024 {
+            values[i] = (i % 100) as f32 * 0.5;
+        }
+
+        let exponents = u32::find_exponents(&values);
+        let mut encoded = [0u32; 1024];
+        let n = Alp::alp_encode(
+            &values,
+            exponents,
+            &mut encoded,
+            &mut [0; 1024],
+            &mut [0.0; 1024],
+        );
+        assert_eq!(n, 0);
+
+        let mut decoded = [0.0; 1024];
+        Alp::alp_decode(&encoded, exponents, &mut decoded);
+        assert!(values
+            .iter()
+            .zip(&decoded)
+            .all(|(a, b)| a.to_bits() == b.to_bits()));
+
+        let (reference, width) = FoR::analyze(&encoded);
+        assert_eq!(width, 9);
+        let mut packed = [0; 128 * 9 / size_of::<u32>()];
+        unsafe { FoR::unchecked_for_pack(width, &encoded, reference, &mut packed) };
+        unsafe {
+            u32::unchecked_unalp_pack(width, &packed, reference, exponents, &[], &[], &mut decoded);
+        };
+        assert!(values
+            .iter()
+            .zip(&decoded)
+            .all(|(a, b)| a.to_bits() == b.to_bits()));
+    }
+
+    #[test]
+    fn test_alp_all_exceptions() {
+        let values = [f64::NAN; 1024];
+        let mut encoded = [0u64; 1024];
+        let n = Alp::alp_encode(
+            &values,
+            u64::find_exponents(&values),
+            &mut encoded,
+            &mut [0; 1024],
+            &mut [0.0; 1024],
+        );
+        assert_eq!(n, 1024);
+    }
+}
@@ -0,0 +1,388 @@
+use arrayref::{array_mut_ref, array_ref};
+use core::mem::size_of;
+
+use crate::util::{compact_with, sample_index, SAMPLES};
+use crate::{
+    rows_by_index, seq_t, unpack, BitPackWidth, BitPacking, FastLanes, SupportedBitPackWidth,
+};
+use paste::paste;
+
+/// The maximum number of left parts in an [`RdSplit`] dictionary, i.e. codes are at most 3 bits.
+pub const MAX_DICTIONARY_SIZE: usize = 8;
+
+/// Where to split the bits of every float in a vector, and the dictionary for the left parts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RdSplit {
+    /// The number of low bits kept in the right part. The remaining left part is at most 16 bits.
+    pub right_width: usize,
+    pub dictionary: [u16; MAX_DICTIONARY_SIZE],
+    pub dictionary_len: usize,
+}
+
+impl RdSplit {
+    /// The bit-width of the dictionary codes.
+    #[must_use]
+    pub fn code_width(&self) -> usize {
+        (usize::BITS - self.dictionary_len.saturating_sub(1).leading_zeros()) as usize
+    }
+}
+
+/// ALP-RD ("real doubles") encoding of `Self::Float` vectors that do not round-trip through a
+/// decimal representation.
+///
+/// The bits of every float are split into a left part, which is usually shared by many values
+/// and is replaced by a `u16` code into a small dictionary, and a right part of `right_width`
+/// bits. Codes and right parts are then packed with `BitPacking::pack`. Left parts missing from
+/// the dictionary are recorded as (index, left part) exceptions.
+pub trait AlpRd: BitPacking {
+    type Float: Copy;
+
+    /// Picks the split and dictionary that minimize the estimated encoded size of a sample of
+    /// `input`.
+    fn find_split(input: &[Self::Float; 1024]) -> RdSplit;
+
+    /// Returns the number of exceptions written.
+    fn alp_rd_encode(
+        input: &[Self::Float; 1024],
+        split: &RdSplit,
+        codes: &mut [u16; 1024],
+        right: &mut [Self; 1024],
+        exception_indices: &mut [u16; 1024],
+        exception_values: &mut [u16; 1024],
+    ) -> usize;
+
+    /// Decodes `L`-bit packed codes and `R`-bit packed right parts, applying the exceptions.
+    fn unalp_rd_pack<const L: usize, const R: usize>(
+        codes: &[u16; 1024 * L / u16::T],
+        right: &[Self; 1024 * R / Self::T],
+        split: &RdSplit,
+        exception_indices: &[u16],
+        exception_values: &[u16],
+        output: &mut [Self::Float; 1024],
+    ) where
+        BitPackWidth<L>: SupportedBitPackWidth<u16>,
+        BitPackWidth<R>: SupportedBitPackWidth<Self>;
+
+    /// Decodes with the code and right part widths taken from `split`, see `unalp_rd_pack`.
+    ///
+    /// # Safety
+    /// The `codes` slice must be of length `1024 * L / 16` and the `right` slice of length
+    /// `1024 * R / T`, where `L` is the code width, `R` the right part width and `T` the bit-width
+    /// of Self. The output slice must be of exactly length 1024.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_unalp_rd_pack(
+        codes: &[u16],
+        right: &[Self],
+        split: &RdSplit,
+        exception_indices: &[u16],
+        exception_values: &[u16],
+        output: &mut [Self::Float],
+    );
+}
+
+/// Returns the most frequent of `lefts`, in descending order of frequency, and the number of
+/// values not covered by them.
+fn dictionary(lefts: &[u16; SAMPLES]) -> ([u16; MAX_DICTIONARY_SIZE], usize, usize) {
+    let mut distinct = [(0u16, 0usize); SAMPLES];
+    let mut n = 0;
+    for &left in lefts {
+        if let Some((_, count)) = distinct[..n].iter_mut().find(|(value, _)| *value == left) {
+            *count += 1;
+        } else {
+            distinct[n] = (left, 1);
+            n += 1;
+        }
+    }
+    distinct[..n].sort_by(|a, b| b.1.cmp(&a.1));
+
+    let len = n.min(MAX_DICTIONARY_SIZE);
+    let mut dictionary = [0; MAX_DICTIONARY_SIZE];
+    for i in 0..len {
+        dictionary[i] = distinct[i].0;
+    }
+    let exceptions = distinct[len..n].iter().map(|(_, count)| count).sum();
+    (dictionary, len, exceptions)
+}
+
+macro_rules! impl_alp_rd {
+    ($T:ty, $F:ty) => {
+        paste! {
+            impl AlpRd for $T {
+                type Float = $F;
+
+                fn find_split(input: &[$F; 1024]) -> RdSplit {
+                    let mut best = (RdSplit::default(), usize::MAX);
+                    // Prefer the widest right part, and so the smallest dictionary, on ties.
+                    for right_width in (Self::T - 16..Self::T).rev() {
+                        let lefts = core::array::from_fn(|i| {
+                            (input[sample_index(i)].to_bits() >> right_width) as u16
+                        });
+                        let (dictionary, dictionary_len, exceptions) = dictionary(&lefts);
+                        let split = RdSplit { right_width, dictionary, dictionary_len };
+
+                        // Exceptions cost their left part and a u16 index.
+                        let size = SAMPLES * (right_width + split.code_width()) + exceptions * 32;
+                        if size < best.1 {
+                            best = (split, size);
+                        }
+                    }
+                    best.0
+                }
+
+                fn alp_rd_encode(
+                    input: &[$F; 1024],
+                    split: &RdSplit,
+                    codes: &mut [u16; 1024],
+                    right: &mut [Self; 1024],
+                    exception_indices: &mut [u16; 1024],
+                    exception_values: &mut [u16; 1024],
+                ) -> usize {
+                    let dictionary = &split.dictionary[..split.dictionary_len];
+                    let mask = (1 << split.right_width) - 1;
+
+                    compact_with(|n, i| {
+                        let bits = input[i].to_bits();
+                        let left = (bits >> split.right_width) as u16;
+                        right[i] = bits & mask;
+
+                        let code = dictionary.iter().position(|&value| value == left);
+                        codes[i] = code.unwrap_or(0) as u16;
+                        exception_indices[n] = i as u16;
+                        exception_values[n] = left;
+                        code.is_none()
+                    })
+                }
+
+                fn unalp_rd_pack<const L: usize, const R: usize>(
+                    codes: &[u16; 1024 * L / u16::T],
+                    right: &[Self; 1024 * R / Self::T],
+                    split: &RdSplit,
+                    exception_indices: &[u16],
+                    exception_values: &[u16],
+                    output: &mut [$F; 1024],
+                ) where
+                    BitPackWidth<L>: SupportedBitPackWidth<u16>,
+                    BitPackWidth<R>: SupportedBitPackWidth<Self>,
+                {
+                    debug_assert_eq!(split.right_width, R);
+                    [<unsplit_ $F>]::<L, R>(codes, right, &split.dictionary, exception_indices, exception_values, output);
+                }
+
+                unsafe fn unchecked_unalp_rd_pack(
+                    codes: &[u16],
+                    right: &[Self],
+                    split: &RdSplit,
+                    exception_indices: &[u16],
+                    exception_values: &[u16],
+                    output: &mut [$F],
+                ) {
+                    let width = split.right_width;
+                    debug_assert_eq!(codes.len(), 64 * split.code_width(), "Codes buffer must be of size 1024 * L / 16");
+                    debug_assert_eq!(right.len(), 128 * width / size_of::<Self>(), "Right buffer must be of size 1024 * R / T");
+                    debug_assert_eq!(output.len(), 1024, "Output buffer must be of size 1024");
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    let output = array_mut_ref![output, 0, 1024];
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => [<unchecked_unsplit_ $F>]::<W>(
+                                codes,
+                                array_ref![right, 0, 1024 * W / <$T>::T],
+                                split,
+                                exception_indices,
+                                exception_values,
+                                output,
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => [<unchecked_unsplit_ $F>]::<{ Self::T }>(
+                                codes,
+                                array_ref![right, 0, 1024],
+                                split,
+                                exception_indices,
+                                exception_values,
+                                output,
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+            }
+
+            /// Dispatches on the code width of `split`, see `unchecked_unalp_rd_pack`.
+            fn [<unchecked_unsplit_ $F>]<const R: usize>(
+                codes: &[u16],
+                right: &[$T; 1024 * R / <$T>::T],
+                split: &RdSplit,
+                exception_indices: &[u16],
+                exception_values: &[u16],
+                output: &mut [$F; 1024],
+            ) where
+                BitPackWidth<R>: SupportedBitPackWidth<$T>,
+            {
+                let (dictionary, indices, values) = (&split.dictionary, exception_indices, exception_values);
+                match split.code_width() {
+                    0 => [<unsplit_ $F>]::<0, R>(array_ref![codes, 0, 0], right, dictionary, indices, values, output),
+                    1 => [<unsplit_ $F>]::<1, R>(array_ref![codes, 0, 64], right, dictionary, indices, values, output),
+                    2 => [<unsplit_ $F>]::<2, R>(array_ref![codes, 0, 128], right, dictionary, indices, values, output),
+                    3 => [<unsplit_ $F>]::<3, R>(array_ref![codes, 0, 192], right, dictionary, indices, values, output),
+                    width => unreachable!("Unsupported code width: {}", width),
+                }
+            }
+
+            /// Joins the left parts looked up from the codes with the right parts in a single pass
+            /// over the lanes of `$T`, then patches the exceptions.
+            ///
+            /// The rows of a lane of `$T` are spread over the code lanes congruent to it modulo
+            /// `<$T>::LANES`, so each lane first gathers its `<$T>::T` left parts from those.
+            fn [<unsplit_ $F>]<const L: usize, const R: usize>(
+                codes: &[u16; 1024 * L / u16::T],
+                right: &[$T; 1024 * R / <$T>::T],
+                dictionary: &[u16; MAX_DICTIONARY_SIZE],
+                exception_indices: &[u16],
+                exception_values: &[u16],
+                output: &mut [$F; 1024],
+            ) where
+                BitPackWidth<L>: SupportedBitPackWidth<u16>,
+                BitPackWidth<R>: SupportedBitPackWidth<$T>,
+            {
+                const ROWS: [u8; 1024] = rows_by_index::<$T>();
+                debug_assert_eq!(exception_indices.len(), exception_values.len());
+
+                for lane in 0..<$T>::LANES {
+                    let mut left = [0u16; <$T>::T];
+                    for code_lane in (lane..u16::LANES).step_by(<$T>::LANES) {
+                        unpack!(u16, L, codes, code_lane, |$idx, $elem| {
+                            left[ROWS[$idx] as usize] = dictionary[$elem as usize]
+                        });
+                    }
+                    unpack!($T, R, right, lane, |$idx, $elem| {
+                        output[$idx] = <$F>::from_bits((<$T>::from(left[ROWS[$idx] as usize]) << R) | $elem)
+                    });
+                }
+
+                let right_mask: $T = (1 << R) - 1;
+                for (&idx, &value) in exception_indices.iter().zip(exception_values) {
+                    let right = output[idx as usize].to_bits() & right_mask;
+                    output[idx as usize] = <$F>::from_bits((<$T>::from(value) << R) | right);
+                }
+            }
+        }
+    };
+}
+
+impl_alp_rd!(u32, f32);
+impl_alp_rd!(u64, f64);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_bits_eq(a: &[f64; 1024], b: &[f64; 1024]) {
+        assert!(a.iter().zip(b).all(|(a, b)| a.to_bits() == b.to_bits()));
+    }
+
+    #[test]
+    fn test_alp_rd() {
+        const L: usize = 1;
+        const R: usize = 52;
+
+        // Full-precision values in [1, 4), so every left part but the two exceptions is one of
+        // two exponents.
+        let mut values = [0f64; 1024];
+        for i in 0..1024 {
+            let mantissa = (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 12;
+            values[i] = f64::from_bits((0x3FF << 52) + ((i as u64 % 2) << 52) + mantissa);
+        }
+        values[100] = 1e300;
+        values[200] = -2.5;
+
+        let split = u64::find_split(&values);
+        assert_eq!((split.right_width, split.code_width()), (R, L));
+
+        let mut codes = [0; 1024];
+        let mut right = [0u64; 1024];
+        let mut exception_indices = [0; 1024];
+        let mut exception_values = [0; 1024];
+        let n = AlpRd::alp_rd_encode(
+            &values,
+            &split,
+            &mut codes,
+            &mut right,
+            &mut exception_indices,
+            &mut exception_values,
+        );
+        assert_eq!(exception_indices[..n], [100, 200]);
+
+        let mut packed_codes = [0; 128 * L / size_of::<u16>()];
+        BitPacking::pack::<L>(&codes, &mut packed_codes);
+        let mut packed_right = [0; 128 * R / size_of::<u64>()];
+        BitPacking::pack::<R>(&right, &mut packed_right);
+
+        let mut decoded = [0.0; 1024];
+        AlpRd::unalp_rd_pack::<L, R>(
+            &packed_codes,
+            &packed_right,
+            &split,
+            &exception_indices[..n],
+            &exception_values[..n],
+            &mut decoded,
+        );
+        assert_bits_eq(&values, &decoded);
+
+        let mut unchecked = [0.0; 1024];
+        unsafe {
+            u64::unchecked_unalp_rd_pack(
+                &packed_codes,
+                &packed_right,
+                &split,
+                &exception_indices[..n],
+                &exception_values[..n],
+                &mut unchecked,
+            );
+        }
+        assert_bits_eq(&values, &unchecked);
+    }
+
+    #[test]
+    fn test_alp_rd_f32_constant() {
+        let values = [core::f32::consts::E; 1024];
+        let split = u32::find_split(&values);
+        assert_eq!(split.code_width(), 0);
+
+        let mut codes = [0; 1024];
+        let mut right = [0u32; 1024];
+        let n = AlpRd::alp_rd_encode(
+            &values,
+            &split,
+            &mut codes,
+            &mut right,
+            &mut [0; 1024],
+            &mut [0; 1024],
+        );
+        assert_eq!(n, 0);
+
+        let mut packed_right = [0; 1024];
+        unsafe {
+            BitPacking::unchecked_pack(
+                split.right_width,
+                &right,
+                &mut packed_right[..32 * split.right_width],
+            );
+        };
+        let mut decoded = [0.0; 1024];
+        unsafe {
+            u32::unchecked_unalp_rd_pack(
+                &[],
+                &packed_right[..32 * split.right_width],
+                &split,
+                &[],
+                &[],
+                &mut decoded,
+            );
+        }
+        assert!(values
+            .iter()
+            .zip(&decoded)
+            .all(|(a, b)| a.to_bits() == b.to_bits()));
+    }
+}
@@ -1,9 +1,18 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 use arrayref::{array_mut_ref, array_ref};
 use const_for::const_for;
 use core::mem::size_of;
 use paste::paste;
 
-use crate::{pack, seq_t, unpack, FastLanes, Pred, Satisfied, FL_ORDER};
+use crate::{
+    pack, selected_indices, seq_t, unpack, FastLanes, Mask1024, Positions, Pred, Satisfied, ZigZag,
+    FL_ORDER,
+};
+
+/// Up to this many elements, `unpack_selected` and `unpack_many` unpack them one by one rather
+/// than unpacking the whole vector.
+pub(crate) const SPARSE_SELECTION: usize = 16;
 
 pub struct BitPackWidth<const W: usize>;
 pub trait SupportedBitPackWidth<T> {}
@@ -12,8 +21,20 @@ impl<const W: usize, T> SupportedBitPackWidth<T> for BitPackWidth<W> where
 {
 }
 
+/// The width and logical length of a slice packed by one of the `*_pack_slice` functions, which
+/// pad the final partial chunk to 1024 elements.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SliceHeader {
+    pub width: usize,
+    pub len: usize,
+}
+
 /// `BitPack` into a compile-time known bit-width.
 pub trait BitPacking: FastLanes {
+    /// Returns the smallest bit-width `W` that all 1024 elements fit into.
+    fn min_width(input: &[Self; 1024]) -> usize;
+
     /// Packs 1024 elements into W bits each.
     /// The output is given as Self to ensure correct alignment.
     fn pack<const W: usize>(input: &[Self; 1024], output: &mut [Self; 1024 * W / Self::T])
@@ -56,12 +77,130 @@ pub trait BitPacking: FastLanes {
     /// is the packed width. The output slice must be of exactly length 1024.
     /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
     unsafe fn unchecked_unpack_single(width: usize, input: &[Self], index: usize) -> Self;
+
+    /// Unpacks the elements at `indices` from a packed array of 1024 `W` bit elements into the
+    /// corresponding positions of `output`.
+    ///
+    /// A few indices are extracted one by one in the order of their packed words, whereas more
+    /// than `unpack_selected` would extract individually unpack the whole vector and gather from
+    /// it.
+    ///
+    /// # Panics
+    /// Panics if `indices` and `output` differ in length, or an index is not less than 1024.
+    fn unpack_many<const W: usize>(
+        packed: &[Self; 1024 * W / Self::T],
+        indices: &[u16],
+        output: &mut [Self],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Unpacks the elements at `indices` from a packed array of 1024 `W` bit elements, where `W`
+    /// is runtime-known instead of compile-time known.
+    ///
+    /// # Safety
+    /// The input slice must be of length `1024 * W / T`, where `T` is the bit-width of Self and `W`
+    /// is the packed width.
+    /// This length is checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_unpack_many(
+        width: usize,
+        input: &[Self],
+        indices: &[u16],
+        output: &mut [Self],
+    );
+
+    /// Unpacks the elements `start..end` of a packed array of 1024 `W` bit elements into `output`
+    /// in index order. Only the packed words holding rows of the range are read.
+    ///
+    /// # Panics
+    /// Panics if `start..end` is not within `0..1024`, or `output` is not of length `end - start`.
+    fn unpack_range<const W: usize>(
+        packed: &[Self; 1024 * W / Self::T],
+        start: usize,
+        end: usize,
+        output: &mut [Self],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Unpacks the elements `start..end` of a packed array of 1024 `W` bit elements, where `W` is
+    /// runtime-known instead of compile-time known.
+    ///
+    /// # Safety
+    /// The input slice must be of length `1024 * W / T`, where `T` is the bit-width of Self and `W`
+    /// is the packed width.
+    /// This length is checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_unpack_range(
+        width: usize,
+        input: &[Self],
+        start: usize,
+        end: usize,
+        output: &mut [Self],
+    );
+
+    /// Unpacks the elements selected by `selection`, a mask as written by `Compare`, to the front
+    /// of `output` in ascending index order. Returns the number of selected elements.
+    ///
+    /// Sparse selections are unpacked element by element, anything denser is compacted while
+    /// unpacking the whole vector.
+    fn unpack_selected<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        selection: &Mask1024,
+        output: &mut [Self; 1024],
+    ) -> usize
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Packs a slice of any length into `width` bits per element, appending to `output`.
+    ///
+    /// The input is processed in chunks of 1024 elements and the final partial chunk is padded
+    /// with zeros, so exactly `input.len().div_ceil(1024) * 1024 * width / T` elements are
+    /// appended. Returns the width and logical length to decode them with.
+    #[cfg(feature = "alloc")]
+    fn pack_slice(width: usize, input: &[Self], output: &mut Vec<Self>) -> SliceHeader;
+
+    /// Unpacks the elements described by `header` packed by `pack_slice`, appending exactly
+    /// `header.len` elements to `output`.
+    #[cfg(feature = "alloc")]
+    fn unpack_slice(header: SliceHeader, input: &[Self], output: &mut Vec<Self>);
+
+    /// Packs 1024 signed elements into `W` bits each, zigzag encoding them so that values of
+    /// small magnitude stay narrow regardless of their sign.
+    fn pack_signed<const W: usize>(
+        input: &[<Self as ZigZag>::Signed; 1024],
+        output: &mut [Self; 1024 * W / Self::T],
+    ) where
+        Self: ZigZag,
+        BitPackWidth<W>: SupportedBitPackWidth<Self>,
+    {
+        Self::zigzag_pack::<W>(input, output);
+    }
+
+    /// Unpacks 1024 signed elements from `W` bits each, as packed by `pack_signed`.
+    fn unpack_signed<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        output: &mut [<Self as ZigZag>::Signed; 1024],
+    ) where
+        Self: ZigZag,
+        BitPackWidth<W>: SupportedBitPackWidth<Self>,
+    {
+        Self::unzigzag_pack::<W>(input, output);
+    }
 }
 
 macro_rules! impl_packing {
     ($T:ty) => {
         paste! {
             impl BitPacking for $T {
+                fn min_width(input: &[Self; 1024]) -> usize {
+                    let mut or = [0; Self::LANES];
+                    for row in 0..Self::T {
+                        for lane in 0..Self::LANES {
+                            or[lane] |= input[Self::LANES * row + lane];
+                        }
+                    }
+                    let or = or.iter().fold(0, |acc, &lane| acc | lane);
+                    Self::T - or.leading_zeros() as usize
+                }
+
                 fn pack<const W: usize>(
                     input: &[Self; 1024],
                     output: &mut [Self; 1024 * W / Self::T],
@@ -133,11 +272,6 @@ macro_rules! impl_packing {
                 where
                     BitPackWidth<W>: SupportedBitPackWidth<Self>,
                 {
-                    if W == 0 {
-                        // Special case for W=0, we just need to zero the output.
-                        return 0 as $T;
-                    }
-
                     // We can think of the input array as effectively a row-major, left-to-right
                     // 2-D array of with `Self::LANES` columns and `Self::T` rows.
                     //
@@ -150,32 +284,91 @@ macro_rules! impl_packing {
                     //
                     // First step, we need to get the lane and row for interpretation #1 above.
                     assert!(index < 1024, "Index must be less than 1024, got {}", index);
-                    let (lane, row): (usize, usize) = {
-                        const LANES: [u8; 1024] = lanes_by_index::<$T>();
-                        const ROWS: [u8; 1024] = rows_by_index::<$T>();
-                        (LANES[index] as usize, ROWS[index] as usize)
-                    };
-
-                    if W == <$T>::T {
-                        // Special case for W==T, we can just read the value directly
-                        return packed[<$T>::LANES * row + lane];
+                    let (lane, row) = ([<LANES_ $T:upper>][index] as usize, [<ROWS_ $T:upper>][index] as usize);
+
+                    [<extract_ $T>]::<W>(packed, lane, row)
+                }
+
+                fn unpack_many<const W: usize>(
+                    packed: &[Self; 1024 * W / Self::T],
+                    indices: &[u16],
+                    output: &mut [Self],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    assert_eq!(indices.len(), output.len(), "Indices and output must be of the same length");
+                    if indices.len() > SPARSE_SELECTION {
+                        let mut unpacked = [0; 1024];
+                        Self::unpack::<W>(packed, &mut unpacked);
+                        for (value, &index) in output.iter_mut().zip(indices) {
+                            *value = unpacked[index as usize];
+                        }
+                        return;
+                    }
+
+                    // Look up every lane and row once, and visit them in the order of their packed
+                    // words, i.e. by row and then by lane.
+                    let mut order = [(0u16, 0u8); SPARSE_SELECTION];
+                    for (i, &index) in indices.iter().enumerate() {
+                        let index = index as usize;
+                        assert!(index < 1024, "Index must be less than 1024, got {}", index);
+                        let position = [<ROWS_ $T:upper>][index] as usize * Self::LANES + [<LANES_ $T:upper>][index] as usize;
+                        order[i] = (position as u16, i as u8);
                     }
+                    let order = &mut order[..indices.len()];
+                    order.sort_unstable();
+                    for &(position, i) in order.iter() {
+                        let (row, lane) = (position as usize / Self::LANES, position as usize % Self::LANES);
+                        output[i as usize] = [<extract_ $T>]::<W>(packed, lane, row);
+                    }
+                }
 
-                    let mask: $T = (1 << (W % <$T>::T)) - 1;
-                    let start_bit = row * W;
-                    let start_word = start_bit / <$T>::T;
-                    let lo_shift = start_bit % <$T>::T;
-                    let remaining_bits = <$T>::T - lo_shift;
-
-                    let lo = packed[<$T>::LANES * start_word + lane] >> lo_shift;
-                    return if remaining_bits >= W {
-                        // in this case we will mask out all bits of hi word
-                        lo & mask
-                    } else {
-                        // guaranteed that lo_shift > 0 and thus remaining_bits < T
-                        let hi = packed[<$T>::LANES * (start_word + 1) + lane] << remaining_bits;
-                        (lo | hi) & mask
-                    };
+                fn unpack_range<const W: usize>(
+                    packed: &[Self; 1024 * W / Self::T],
+                    start: usize,
+                    end: usize,
+                    output: &mut [Self],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    assert!(start <= end && end <= 1024, "Range {}..{} must be within 0..1024", start, end);
+                    assert_eq!(output.len(), end - start, "Output must be of the same length as the range");
+                    let mut rows = [0; Self::LANES];
+                    range_rows(&[<ROWS_ $T:upper>], start, end, &mut rows);
+                    for lane in 0..Self::LANES {
+                        [<unpack_rows_ $T>]::<W>(packed, lane, rows[lane], |index, elem| {
+                            output[index - start] = elem;
+                        });
+                    }
+                }
+
+                fn unpack_selected<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    selection: &Mask1024,
+                    output: &mut [Self; 1024],
+                ) -> usize
+                where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    let count = selection.count_ones();
+                    if count <= SPARSE_SELECTION {
+                        let mut indices = [0; SPARSE_SELECTION];
+                        selected_indices(selection, &mut indices);
+                        for (i, &index) in indices[..count].iter().enumerate() {
+                            output[i] = Self::unpack_single::<W>(input, index as usize);
+                        }
+                        return count;
+                    }
+
+                    let positions = Positions::new(selection);
+                    for lane in 0..Self::LANES {
+                        unpack!($T, W, input, lane, |$idx, $elem| {
+                            if let Some(n) = positions.get($idx) {
+                                output[n] = $elem;
+                            }
+                        });
+                    }
+                    count
                 }
 
                 unsafe fn unchecked_unpack_single(width: usize, packed: &[Self], index: usize) -> Self {
@@ -198,11 +391,189 @@ macro_rules! impl_packing {
                         }
                     })
                 }
+
+                unsafe fn unchecked_unpack_many(width: usize, packed: &[Self], indices: &[u16], output: &mut [Self]) {
+                    const T: usize = <$T>::T;
+
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(packed.len(), packed_len, "Input buffer must be of size {}", packed_len);
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => <$T>::unpack_many::<W>(array_ref![packed, 0, 1024 * W / T], indices, output),)*
+                            // seq_t has exclusive upper bound
+                            T => <$T>::unpack_many::<T>(array_ref![packed, 0, 1024], indices, output),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
+                unsafe fn unchecked_unpack_range(width: usize, packed: &[Self], start: usize, end: usize, output: &mut [Self]) {
+                    const T: usize = <$T>::T;
+
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(packed.len(), packed_len, "Input buffer must be of size {}", packed_len);
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => <$T>::unpack_range::<W>(array_ref![packed, 0, 1024 * W / T], start, end, output),)*
+                            // seq_t has exclusive upper bound
+                            T => <$T>::unpack_range::<T>(array_ref![packed, 0, 1024], start, end, output),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
+                #[cfg(feature = "alloc")]
+                fn pack_slice(width: usize, input: &[Self], output: &mut Vec<Self>) -> SliceHeader {
+                    assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+                    let packed_len = 128 * width / size_of::<Self>();
+                    output.reserve(input.len().div_ceil(1024) * packed_len);
+
+                    let mut chunks = input.chunks_exact(1024);
+                    for chunk in &mut chunks {
+                        let start = output.len();
+                        output.resize(start + packed_len, 0);
+                        unsafe { Self::unchecked_pack(width, chunk, &mut output[start..]) };
+                    }
+
+                    let tail = chunks.remainder();
+                    if !tail.is_empty() {
+                        let mut padded = [0; 1024];
+                        padded[..tail.len()].copy_from_slice(tail);
+                        let start = output.len();
+                        output.resize(start + packed_len, 0);
+                        unsafe { Self::unchecked_pack(width, &padded, &mut output[start..]) };
+                    }
+                    SliceHeader { width, len: input.len() }
+                }
+
+                #[cfg(feature = "alloc")]
+                fn unpack_slice(header: SliceHeader, input: &[Self], output: &mut Vec<Self>) {
+                    let SliceHeader { width, len } = header;
+                    assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+                    let packed_len = 128 * width / size_of::<Self>();
+                    assert_eq!(input.len(), len.div_ceil(1024) * packed_len, "Input buffer must hold {} packed chunks", len.div_ceil(1024));
+                    output.reserve(len);
+
+                    for i in 0..len / 1024 {
+                        let start = output.len();
+                        output.resize(start + 1024, 0);
+                        unsafe {
+                            Self::unchecked_unpack(width, &input[i * packed_len..][..packed_len], &mut output[start..])
+                        };
+                    }
+
+                    let tail = len % 1024;
+                    if tail > 0 {
+                        let mut unpacked = [0; 1024];
+                        unsafe { Self::unchecked_unpack(width, &input[len / 1024 * packed_len..], &mut unpacked) };
+                        output.extend_from_slice(&unpacked[..tail]);
+                    }
+                }
+            }
+
+            /// Streams the `W` bit elements at the rows of `lane` set in `rows` to `f` with their
+            /// indices, in ascending row order. Only the packed words holding those rows are read,
+            /// each once.
+            #[inline]
+            pub(crate) fn [<unpack_rows_ $T>]<const W: usize>(
+                packed: &[$T; 1024 * W / <$T>::T],
+                lane: usize,
+                mut rows: u64,
+                mut f: impl FnMut(usize, $T),
+            ) where
+                BitPackWidth<W>: SupportedBitPackWidth<$T>,
+            {
+                const T: usize = <$T>::T;
+                let mask: $T = if W == T { <$T>::MAX } else { (1 << (W % T)) - 1 };
+                // The last packed word read, and its position.
+                let (mut src, mut word): ($T, usize) = (0, usize::MAX);
+                while rows != 0 {
+                    let row = rows.trailing_zeros() as usize;
+                    rows &= rows - 1;
+                    let index = $crate::__index__!(@transposed $T, row, lane);
+                    if W == 0 {
+                        f(index, 0);
+                        continue;
+                    }
+
+                    let (start_word, shift) = (row * W / T, row * W % T);
+                    if start_word != word {
+                        src = packed[<$T>::LANES * start_word + lane];
+                        word = start_word;
+                    }
+                    let mut elem = src >> shift;
+                    if shift + W > T {
+                        // The element continues in the next packed word.
+                        word += 1;
+                        src = packed[<$T>::LANES * word + lane];
+                        elem |= src << (T - shift);
+                    }
+                    f(index, elem & mask);
+                }
+            }
+
+            // helper tables executed at compile-time to speed up unpack_single at runtime
+            const [<LANES_ $T:upper>]: [u8; 1024] = lanes_by_index::<$T>();
+            const [<ROWS_ $T:upper>]: [u8; 1024] = rows_by_index::<$T>();
+
+            /// Extracts the `W` bit element at (`lane`, `row`) of a packed array.
+            #[inline]
+            fn [<extract_ $T>]<const W: usize>(packed: &[$T; 1024 * W / <$T>::T], lane: usize, row: usize) -> $T
+            where
+                BitPackWidth<W>: SupportedBitPackWidth<$T>,
+            {
+                if W == 0 {
+                    // Special case for W=0, we just need to zero the output.
+                    return 0 as $T;
+                }
+
+                if W == <$T>::T {
+                    // Special case for W==T, we can just read the value directly
+                    return packed[<$T>::LANES * row + lane];
+                }
+
+                let mask: $T = (1 << (W % <$T>::T)) - 1;
+                let start_bit = row * W;
+                let start_word = start_bit / <$T>::T;
+                let lo_shift = start_bit % <$T>::T;
+                let remaining_bits = <$T>::T - lo_shift;
+
+                let lo = packed[<$T>::LANES * start_word + lane] >> lo_shift;
+                return if remaining_bits >= W {
+                    // in this case we will mask out all bits of hi word
+                    lo & mask
+                } else {
+                    // guaranteed that lo_shift > 0 and thus remaining_bits < T
+                    let hi = packed[<$T>::LANES * (start_word + 1) + lane] << remaining_bits;
+                    (lo | hi) & mask
+                };
             }
         }
     };
 }
 
+/// Sets the bit of every row that holds an element of `start..end` in the mask of its lane in
+/// `lanes`, given the row of every index as written by `rows_by_index`.
+pub(crate) fn range_rows(rows: &[u8; 1024], start: usize, end: usize, lanes: &mut [u64]) {
+    for index in start..end {
+        lanes[index % lanes.len()] |= 1 << rows[index];
+    }
+}
+
+/// The rows up to and including the last one set in `rows`, i.e. those that a prefix sum over a
+/// lane has to decode to reach all of them.
+pub(crate) const fn prefix_rows(rows: u64) -> u64 {
+    if rows == 0 {
+        0
+    } else {
+        u64::MAX >> rows.leading_zeros()
+    }
+}
+
 // helper function executed at compile-time to speed up unpack_single at runtime
 const fn lanes_by_index<T: FastLanes>() -> [u8; 1024] {
     let mut lanes = [0u8; 1024];
@@ -213,7 +584,7 @@ const fn lanes_by_index<T: FastLanes>() -> [u8; 1024] {
 }
 
 // helper function executed at compile-time to speed up unpack_single at runtime
-const fn rows_by_index<T: FastLanes>() -> [u8; 1024] {
+pub(crate) const fn rows_by_index<T: FastLanes>() -> [u8; 1024] {
     let mut rows = [0u8; 1024];
     const_for!(i in 0..1024 => {
         // This is the inverse of the `index` function from the pack/unpack macros:
@@ -255,6 +626,110 @@ mod test {
         assert_eq!(input, output);
     }
 
+    #[test]
+    fn test_unpack_selected() {
+        const W: usize = 11;
+        let values: [u32; 1024] = array::from_fn(|i| (i * 13 % 2000) as u32);
+        let mut packed = [0; 128 * W / size_of::<u32>()];
+        BitPacking::pack::<W>(&values, &mut packed);
+
+        // A sparse selection takes the single-value path, a dense one the full unpack.
+        for threshold in [5, 1000] {
+            let mut selection = Mask1024::NONE;
+            crate::Compare::lt::<W>(&packed, 0, threshold, &mut selection);
+            let mut output = [0; 1024];
+            let n = BitPacking::unpack_selected::<W>(&packed, &selection, &mut output);
+            assert!(output[..n]
+                .iter()
+                .eq(values.iter().filter(|&&v| v < threshold)));
+        }
+    }
+
+    #[test]
+    fn test_unpack_many() {
+        const W: usize = 13;
+        let values: [u16; 1024] = array::from_fn(|i| (i * 97 % 8192) as u16);
+        let mut packed = [0; 128 * W / size_of::<u16>()];
+        BitPacking::pack::<W>(&values, &mut packed);
+
+        // Few indices are extracted individually, many from a full unpack.
+        let indices: [u16; 100] = array::from_fn(|i| (1023 - i * 37 % 1024) as u16);
+        for n in [0, 3, 16, 17, 100] {
+            let indices = &indices[..n];
+            let mut output = [0; 100];
+            BitPacking::unpack_many::<W>(&packed, indices, &mut output[..n]);
+            assert!(output[..n]
+                .iter()
+                .eq(indices.iter().map(|&i| &values[i as usize])));
+
+            let mut unchecked = [0; 100];
+            unsafe { BitPacking::unchecked_unpack_many(W, &packed, indices, &mut unchecked[..n]) };
+            assert_eq!(output, unchecked);
+        }
+
+        let mut output = [1; 2];
+        BitPacking::unpack_many::<0>(&[0u64; 0], &[5, 1000], &mut output);
+        assert_eq!(output, [0, 0]);
+    }
+
+    #[test]
+    fn test_unpack_range() {
+        const W: usize = 11;
+        let values: [u32; 1024] = array::from_fn(|i| (i * 31 % 2048) as u32);
+        let mut packed = [0; 128 * W / size_of::<u32>()];
+        BitPacking::pack::<W>(&values, &mut packed);
+
+        for (start, end) in [(0, 0), (0, 1024), (5, 6), (100, 700), (1000, 1024)] {
+            let mut output = [0; 1024];
+            BitPacking::unpack_range::<W>(&packed, start, end, &mut output[..end - start]);
+            assert_eq!(output[..end - start], values[start..end]);
+
+            let mut unchecked = [0; 1024];
+            unsafe {
+                BitPacking::unchecked_unpack_range(
+                    W,
+                    &packed,
+                    start,
+                    end,
+                    &mut unchecked[..end - start],
+                );
+            };
+            assert_eq!(output, unchecked);
+        }
+
+        // Every width, with ranges that only hit a few rows of each lane.
+        for width in 0..=32 {
+            let values: [u32; 1024] = array::from_fn(|i| ((i as u64) & ((1 << width) - 1)) as u32);
+            let mut packed = [0; 1024];
+            let packed = &mut packed[..128 * width / size_of::<u32>()];
+            unsafe { BitPacking::unchecked_pack(width, &values, packed) };
+            for (start, end) in [(0, 64), (31, 33), (992, 1024)] {
+                let mut output = [0; 64];
+                unsafe {
+                    BitPacking::unchecked_unpack_range(
+                        width,
+                        packed,
+                        start,
+                        end,
+                        &mut output[..end - start],
+                    );
+                };
+                assert_eq!(output[..end - start], values[start..end]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_min_width() {
+        assert_eq!(BitPacking::min_width(&[0u8; 1024]), 0);
+        assert_eq!(BitPacking::min_width(&[u64::MAX; 1024]), 64);
+
+        let mut values = [1u16; 1024];
+        assert_eq!(BitPacking::min_width(&values), 1);
+        values[777] = 1000;
+        assert_eq!(BitPacking::min_width(&values), 10);
+    }
+
     #[test]
     fn test_unpack_single() {
         let values = array::from_fn(|i| i as u32);
@@ -268,6 +743,9 @@ mod test {
                 values[i]
             );
         }
+
+        // Zero-width vectors are all zeros.
+        assert_eq!(BitPacking::unpack_single::<0>(&[0u32; 0], 1023), 0);
     }
 
     fn try_round_trip<T: BitPacking + Debug, const W: usize>()
@@ -309,6 +787,25 @@ mod test {
         };
     }
 
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_pack_slice() {
+        use alloc::vec::Vec;
+
+        for len in [0usize, 1, 1000, 1024, 1025, 3000] {
+            let values: Vec<u32> = (0..len).map(|i| (i % 1000) as u32).collect();
+
+            let mut packed = Vec::new();
+            let header = BitPacking::pack_slice(10, &values, &mut packed);
+            assert_eq!(header, SliceHeader { width: 10, len });
+            assert_eq!(packed.len(), len.div_ceil(1024) * 320);
+
+            let mut unpacked = Vec::new();
+            BitPacking::unpack_slice(header, &packed, &mut unpacked);
+            assert_eq!(values, unpacked);
+        }
+    }
+
     seq!(W in 0..=8 { impl_try_round_trip!(u8, W); });
     seq!(W in 0..=16 { impl_try_round_trip!(u16, W); });
     seq!(W in 0..=32 { impl_try_round_trip!(u32, W); });
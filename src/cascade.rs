@@ -0,0 +1,322 @@
+use alloc::vec::Vec;
+
+use core::mem::size_of;
+
+use crate::{Delta, FoR, SliceHeader, Transpose};
+
+/// An encoding the [`Compressor`] can choose for a 1024-element chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Scheme {
+    BitPacked,
+    FoR,
+    Delta,
+}
+
+impl Scheme {
+    /// All schemes, in the order they are preferred when several compress equally well.
+    pub const ALL: [Scheme; 3] = [Scheme::BitPacked, Scheme::FoR, Scheme::Delta];
+}
+
+/// A single compressed chunk, tagged with the scheme that produced it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Chunk<T> {
+    BitPacked {
+        width: usize,
+        packed: Vec<T>,
+    },
+    FoR {
+        width: usize,
+        reference: T,
+        packed: Vec<T>,
+    },
+    /// Delta over the transposed chunk, with `T::LANES` bases.
+    Delta {
+        width: usize,
+        bases: Vec<T>,
+        packed: Vec<T>,
+    },
+}
+
+impl<T> Chunk<T> {
+    #[must_use]
+    pub fn scheme(&self) -> Scheme {
+        match self {
+            Chunk::BitPacked { .. } => Scheme::BitPacked,
+            Chunk::FoR { .. } => Scheme::FoR,
+            Chunk::Delta { .. } => Scheme::Delta,
+        }
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        match self {
+            Chunk::BitPacked { width, .. }
+            | Chunk::FoR { width, .. }
+            | Chunk::Delta { width, .. } => *width,
+        }
+    }
+}
+
+/// A sequence of `len` values compressed as `len.div_ceil(1024)` independently encoded chunks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Compressed<T> {
+    len: usize,
+    chunks: Vec<Chunk<T>>,
+}
+
+impl<T> Compressed<T> {
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub fn chunks(&self) -> &[Chunk<T>] {
+        &self.chunks
+    }
+}
+
+/// Picks the smallest of a set of [`Scheme`]s for every chunk.
+///
+/// The choice is made from cheap statistics (see `BitPacking::min_width`, `FoR::analyze` and
+/// `Delta::analyze`) rather than by trial encoding, so every chunk is only packed once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Compressor {
+    schemes: Vec<Scheme>,
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self::new(&Scheme::ALL)
+    }
+}
+
+impl Compressor {
+    /// Creates a compressor that only considers `schemes`, preferring earlier ones on ties.
+    ///
+    /// # Panics
+    /// Panics if `schemes` is empty.
+    #[must_use]
+    pub fn new(schemes: &[Scheme]) -> Self {
+        assert!(!schemes.is_empty(), "At least one scheme is required");
+        Self {
+            schemes: schemes.to_vec(),
+        }
+    }
+
+    /// Compresses naturally ordered `values`.
+    pub fn compress<T: Delta + FoR>(&self, values: &[T]) -> Compressed<T>
+    where
+        [(); T::LANES]:,
+    {
+        Compressed {
+            len: values.len(),
+            chunks: values
+                .chunks(1024)
+                .map(|chunk| self.compress_chunk(chunk))
+                .collect(),
+        }
+    }
+
+    /// Compresses up to 1024 naturally ordered values into a single chunk.
+    ///
+    /// # Panics
+    /// Panics if `values` is empty or longer than 1024.
+    pub fn compress_chunk<T: Delta + FoR>(&self, values: &[T]) -> Chunk<T>
+    where
+        [(); T::LANES]:,
+    {
+        assert!(
+            (1..=1024).contains(&values.len()),
+            "Chunk length must be between 1 and 1024, got {}",
+            values.len()
+        );
+        // Repeating the last value never widens a scheme: it keeps the range of the values, and as
+        // every transposed lane holds consecutive values, it only adds zero deltas. The Delta bases
+        // do change, but they are stored in full.
+        let mut padded = [values[values.len() - 1]; 1024];
+        padded[..values.len()].copy_from_slice(values);
+
+        // Only the Delta scheme writes these, so they still hold its statistics when it wins.
+        let mut transposed = [T::zero(); 1024];
+        let mut bases = [T::zero(); T::LANES];
+        let mut best = (Scheme::BitPacked, usize::MAX, 0, T::zero());
+        for &scheme in &self.schemes {
+            // Sizes are in bits, including the per-chunk metadata.
+            let (width, reference, size) = match scheme {
+                Scheme::BitPacked => {
+                    let width = T::min_width(&padded);
+                    (width, T::zero(), 1024 * width)
+                }
+                Scheme::FoR => {
+                    let (reference, width) = <T as FoR>::analyze(&padded);
+                    (width, reference, 1024 * width + T::T)
+                }
+                Scheme::Delta => {
+                    Transpose::transpose(&padded, &mut transposed);
+                    let width = <T as Delta>::analyze(&transposed, &mut bases);
+                    (width, T::zero(), 1024 * width + T::LANES * T::T)
+                }
+            };
+            if size < best.1 {
+                best = (scheme, size, width, reference);
+            }
+        }
+
+        let (scheme, _, width, reference) = best;
+        let mut packed = alloc::vec![T::zero(); 128 * width / size_of::<T>()];
+        // SAFETY: `width <= T::T`, `padded` holds 1024 elements and `packed` is sized for them.
+        unsafe {
+            match scheme {
+                Scheme::BitPacked => {
+                    T::unchecked_pack(width, &padded, &mut packed);
+                    Chunk::BitPacked { width, packed }
+                }
+                Scheme::FoR => {
+                    T::unchecked_for_pack(width, &padded, reference, &mut packed);
+                    Chunk::FoR {
+                        width,
+                        reference,
+                        packed,
+                    }
+                }
+                Scheme::Delta => {
+                    T::unchecked_delta_pack(width, &transposed, &bases, &mut packed);
+                    Chunk::Delta {
+                        width,
+                        bases: bases.to_vec(),
+                        packed,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Decodes the output of a [`Compressor`] with the fused unpacking kernels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Decompressor;
+
+impl Decompressor {
+    /// Decodes all values, appending them to `output`.
+    pub fn decompress<T: Delta + FoR>(&self, compressed: &Compressed<T>, output: &mut Vec<T>) {
+        output.reserve(compressed.len);
+        for (i, chunk) in compressed.chunks.iter().enumerate() {
+            let len = (compressed.len - i * 1024).min(1024);
+            self.decompress_chunk(chunk, len, output);
+        }
+    }
+
+    /// Decodes the first `len` values of `chunk`, appending them to `output`.
+    pub fn decompress_chunk<T: Delta + FoR>(
+        &self,
+        chunk: &Chunk<T>,
+        len: usize,
+        output: &mut Vec<T>,
+    ) {
+        let header = SliceHeader {
+            width: chunk.width(),
+            len,
+        };
+        match chunk {
+            Chunk::BitPacked { packed, .. } => T::unpack_slice(header, packed, output),
+            Chunk::FoR {
+                reference, packed, ..
+            } => T::unfor_pack_slice(header, packed, *reference, output),
+            Chunk::Delta { bases, packed, .. } => {
+                T::undelta_pack_slice(header, packed, bases, output);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::fmt::Debug;
+
+    fn round_trip<T: Delta + FoR + Debug>(values: &[T]) -> Compressed<T>
+    where
+        [(); T::LANES]:,
+    {
+        let compressed = Compressor::default().compress(values);
+        assert_eq!(compressed.len(), values.len());
+        assert_eq!(compressed.chunks().len(), values.len().div_ceil(1024));
+
+        let mut decompressed = Vec::new();
+        Decompressor.decompress(&compressed, &mut decompressed);
+        assert_eq!(decompressed, values);
+        compressed
+    }
+
+    #[test]
+    fn test_picks_smallest_scheme() {
+        // Small values, large offset values, then a steadily increasing sequence.
+        let values: Vec<u32> = (0..1024)
+            .map(|i| i % 7)
+            .chain((0..1024).map(|i| 1_000_000 + (i * 31) % 1000))
+            .chain((0..1024).map(|i| 5_000_000 + i * 100))
+            .collect();
+        let compressed = round_trip(&values);
+
+        let chosen: Vec<_> = compressed
+            .chunks()
+            .iter()
+            .map(|c| (c.scheme(), c.width()))
+            .collect();
+        assert_eq!(
+            chosen,
+            [
+                (Scheme::BitPacked, 3),
+                (Scheme::FoR, 10),
+                (Scheme::Delta, 7)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_padded_tail() {
+        // Partial chunks are padded, but pick the same schemes and widths as the full ones.
+        let chunks: [[u32; 1024]; 3] = [
+            core::array::from_fn(|i| (i % 7) as u32),
+            core::array::from_fn(|i| 1_000_000 + ((i * 31) % 1000) as u32),
+            core::array::from_fn(|i| 5_000_000 + (i * 100) as u32),
+        ];
+        let compressor = Compressor::default();
+        for chunk in &chunks {
+            let full = compressor.compress_chunk(chunk);
+            for len in [100, 1000, 1023] {
+                let tail = compressor.compress_chunk(&chunk[..len]);
+                assert_eq!((tail.scheme(), tail.width()), (full.scheme(), full.width()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_restricted_schemes() {
+        let values: Vec<u16> = (0..1500).map(|i| 1000 + i).collect();
+        let compressed = Compressor::new(&[Scheme::BitPacked]).compress(&values);
+        assert!(compressed
+            .chunks()
+            .iter()
+            .all(|c| c.scheme() == Scheme::BitPacked));
+
+        let mut decompressed = Vec::new();
+        Decompressor.decompress(&compressed, &mut decompressed);
+        assert_eq!(decompressed, values);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip::<u8>(&(0..3000u32).map(|i| (i % 256) as u8).collect::<Vec<_>>());
+        round_trip::<u16>(&(0..2000u32).map(|i| (i * 3) as u16).collect::<Vec<_>>());
+        round_trip::<u32>(&[u32::MAX; 10]);
+        round_trip::<u64>(&(0..1025).map(|i| i << 50).collect::<Vec<_>>());
+        round_trip::<u64>(&[]);
+    }
+}
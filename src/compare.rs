@@ -0,0 +1,209 @@
+use crate::{
+    unpack, BitPackWidth, BitPacking, FastLanes, Mask1024, SupportedBitPackWidth, MASK_ROWS,
+};
+use paste::paste;
+
+/// Predicate evaluation directly on packed vectors.
+///
+/// The result is a 1024-bit selection mask in the `FastLanes` layout of 1-bit packed `u64`s, so
+/// that bit `row` of word `lane` selects the element the `u64` kernels visit at (`row`, `lane`),
+/// see [`Mask1024`]. Packed values are compared against the constant translated by
+/// `reference`, so the kernels apply to `FoR` packed vectors as-is, and to plain bit-packed
+/// vectors with a `reference` of zero.
+pub trait Compare: BitPacking {
+    /// Sets the bit of every element for which `predicate` holds on its packed value, and clears
+    /// all other bits.
+    fn compare<const W: usize, P: Fn(Self) -> bool>(
+        input: &[Self; 1024 * W / Self::T],
+        predicate: P,
+        output: &mut Mask1024,
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    fn eq<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: Self,
+        value: Self,
+        output: &mut Mask1024,
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>,
+    {
+        match value.checked_sub(&reference) {
+            Some(value) => Self::compare::<W, _>(input, |elem| elem == value, output),
+            None => *output = Mask1024::NONE,
+        }
+    }
+
+    fn lt<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: Self,
+        value: Self,
+        output: &mut Mask1024,
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>,
+    {
+        match value.checked_sub(&reference) {
+            Some(value) => Self::compare::<W, _>(input, |elem| elem < value, output),
+            None => *output = Mask1024::NONE,
+        }
+    }
+
+    fn le<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: Self,
+        value: Self,
+        output: &mut Mask1024,
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>,
+    {
+        match value.checked_sub(&reference) {
+            Some(value) => Self::compare::<W, _>(input, |elem| elem <= value, output),
+            None => *output = Mask1024::NONE,
+        }
+    }
+
+    fn gt<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: Self,
+        value: Self,
+        output: &mut Mask1024,
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>,
+    {
+        match value.checked_sub(&reference) {
+            Some(value) => Self::compare::<W, _>(input, |elem| elem > value, output),
+            None => *output = Mask1024::ALL,
+        }
+    }
+
+    fn ge<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: Self,
+        value: Self,
+        output: &mut Mask1024,
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>,
+    {
+        match value.checked_sub(&reference) {
+            Some(value) => Self::compare::<W, _>(input, |elem| elem >= value, output),
+            None => *output = Mask1024::ALL,
+        }
+    }
+
+    /// Selects the elements within the inclusive range `low..=high`.
+    fn between<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: Self,
+        low: Self,
+        high: Self,
+        output: &mut Mask1024,
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>,
+    {
+        match high.checked_sub(&reference) {
+            Some(high) => {
+                let low = low.saturating_sub(reference);
+                Self::compare::<W, _>(input, |elem| low <= elem && elem <= high, output);
+            }
+            None => *output = Mask1024::NONE,
+        }
+    }
+}
+
+macro_rules! impl_compare {
+    ($T:ty) => {
+        paste! {
+            impl Compare for $T {
+                #[inline]
+                fn compare<const W: usize, P: Fn(Self) -> bool>(
+                    input: &[Self; 1024 * W / Self::T],
+                    predicate: P,
+                    output: &mut Mask1024,
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    *output = Mask1024::NONE;
+                    for lane in 0..Self::LANES {
+                        unpack!($T, W, input, lane, |$idx, $elem| {
+                            output.0[$idx % 16] |= u64::from(predicate($elem)) << MASK_ROWS[$idx];
+                        });
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_compare!(u8);
+impl_compare!(u16);
+impl_compare!(u32);
+impl_compare!(u64);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FoR;
+    use core::mem::size_of;
+
+    fn selected(mask: &Mask1024) -> [bool; 1024] {
+        (*mask).into()
+    }
+
+    #[test]
+    fn test_compare() {
+        const W: usize = 9;
+        let values: [u16; 1024] = core::array::from_fn(|i| ((i * 37) % 500) as u16);
+        let mut packed = [0; 128 * W / size_of::<u16>()];
+        BitPacking::pack::<W>(&values, &mut packed);
+
+        let mut mask = Mask1024::NONE;
+        Compare::eq::<W>(&packed, 0, 74, &mut mask);
+        assert_eq!(selected(&mask), values.map(|v| v == 74));
+        Compare::lt::<W>(&packed, 0, 100, &mut mask);
+        assert_eq!(selected(&mask), values.map(|v| v < 100));
+        Compare::le::<W>(&packed, 0, 100, &mut mask);
+        assert_eq!(selected(&mask), values.map(|v| v <= 100));
+        Compare::gt::<W>(&packed, 0, 400, &mut mask);
+        assert_eq!(selected(&mask), values.map(|v| v > 400));
+        Compare::ge::<W>(&packed, 0, 400, &mut mask);
+        assert_eq!(selected(&mask), values.map(|v| v >= 400));
+        Compare::between::<W>(&packed, 0, 10, 20, &mut mask);
+        assert_eq!(selected(&mask), values.map(|v| (10..=20).contains(&v)));
+    }
+
+    #[test]
+    fn test_compare_for() {
+        const W: usize = 10;
+        let values: [u64; 1024] = core::array::from_fn(|i| 1_000_000 + i as u64);
+        let mut packed = [0; 128 * W / size_of::<u64>()];
+        FoR::for_pack::<W>(&values, 1_000_000, &mut packed);
+
+        let mut mask = Mask1024::NONE;
+        Compare::eq::<W>(&packed, 1_000_000, 1_000_500, &mut mask);
+        assert_eq!(selected(&mask), values.map(|v| v == 1_000_500));
+        Compare::between::<W>(&packed, 1_000_000, 5, 1_000_010, &mut mask);
+        assert_eq!(selected(&mask), values.map(|v| v <= 1_000_010));
+
+        // Constants below the reference.
+        Compare::lt::<W>(&packed, 1_000_000, 7, &mut mask);
+        assert_eq!(mask, Mask1024::NONE);
+        Compare::ge::<W>(&packed, 1_000_000, 7, &mut mask);
+        assert_eq!(mask, Mask1024::ALL);
+        Compare::between::<W>(&packed, 1_000_000, 0, 7, &mut mask);
+        assert_eq!(mask, Mask1024::NONE);
+    }
+
+    #[test]
+    fn test_mask_layout() {
+        // A mask is exactly a 1-bit packed u64 vector.
+        let values: [u8; 1024] = core::array::from_fn(|i| (i % 3) as u8);
+        let mut packed = [0; 128 * 2];
+        BitPacking::pack::<2>(&values, &mut packed);
+        let mut mask = Mask1024::NONE;
+        Compare::eq::<2>(&packed, 0, 1, &mut mask);
+
+        let mut unpacked = [0u64; 1024];
+        BitPacking::unpack::<1>(&mask.0, &mut unpacked);
+        assert_eq!(unpacked, values.map(|v| u64::from(v == 1)));
+    }
+}
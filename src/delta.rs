@@ -1,25 +1,265 @@
 #![allow(unused_assignments)]
 
-use crate::{iterate, unpack, BitPackWidth, BitPacking, FastLanes, SupportedBitPackWidth};
+#[cfg(feature = "alloc")]
+use crate::SliceHeader;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use arrayref::{array_mut_ref, array_ref};
+use core::mem::size_of;
+
+use crate::{
+    iterate, pack, prefix_rows, range_rows, rows_by_index, selected_indices, seq_t, transpose,
+    unpack, BitPackWidth, BitPacking, FastLanes, Mask1024, Positions, SupportedBitPackWidth,
+    ZigZag, SPARSE_SELECTION,
+};
 use paste::paste;
 
 pub trait Delta: BitPacking {
+    /// Writes the bases that minimize the packed width of a transposed vector, i.e. the first
+    /// row of each lane, and returns the bit-width `W` needed to pack the resulting deltas.
+    fn analyze(input: &[Self; 1024], base: &mut [Self; Self::LANES]) -> usize;
+
     fn delta(input: &[Self; 1024], base: &[Self; Self::LANES], output: &mut [Self; 1024]);
 
     fn undelta(input: &[Self; 1024], base: &[Self; Self::LANES], output: &mut [Self; 1024]);
 
+    /// Delta encodes and packs 1024 transposed elements into `W` bits each in a single pass.
+    fn delta_pack<const W: usize>(
+        input: &[Self; 1024],
+        base: &[Self; Self::LANES],
+        output: &mut [Self; 1024 * W / Self::T],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Delta encodes and packs 1024 elements into `W` bits each, where `W` is runtime-known
+    /// instead of compile-time known.
+    ///
+    /// # Safety
+    /// The input slice must be of exactly length 1024 and the base slice of length `Self::LANES`.
+    /// The output slice must be of length `1024 * W / T`, where `T` is the bit-width of Self and
+    /// `W` is the packed width.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_delta_pack(
+        width: usize,
+        input: &[Self],
+        base: &[Self],
+        output: &mut [Self],
+    );
+
+    /// Delta encodes and packs 1024 elements in natural order, transposing them on the fly
+    /// instead of through a scratch vector. The bases are those of the transposed vector, i.e.
+    /// `base[lane] == input[transpose(lane)]` for the narrowest packing.
+    fn delta_pack_untransposed<const W: usize>(
+        input: &[Self; 1024],
+        base: &[Self; Self::LANES],
+        output: &mut [Self; 1024 * W / Self::T],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Delta encodes and packs 1024 elements in natural order, where `W` is runtime-known instead
+    /// of compile-time known.
+    ///
+    /// # Safety
+    /// The input slice must be of exactly length 1024 and the base slice of length `Self::LANES`.
+    /// The output slice must be of length `1024 * W / T`, where `T` is the bit-width of Self and
+    /// `W` is the packed width.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_delta_pack_untransposed(
+        width: usize,
+        input: &[Self],
+        base: &[Self],
+        output: &mut [Self],
+    );
+
     fn undelta_pack<const W: usize>(
         input: &[Self; 1024 * W / Self::T],
         base: &[Self; Self::LANES],
         output: &mut [Self; 1024],
     ) where
         BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Unpacks and delta decodes 1024 elements from `W` bits each, where `W` is runtime-known
+    /// instead of compile-time known.
+    ///
+    /// # Safety
+    /// The input slice must be of length `1024 * W / T`, where `T` is the bit-width of Self and `W`
+    /// is the packed width. The base slice must be of length `Self::LANES` and the output slice
+    /// must be of exactly length 1024.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_undelta_pack(
+        width: usize,
+        input: &[Self],
+        base: &[Self],
+        output: &mut [Self],
+    );
+
+    /// Unpacks and delta decodes 1024 elements packed by `delta_pack_untransposed`, writing them
+    /// in natural order.
+    fn undelta_pack_untransposed<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        base: &[Self; Self::LANES],
+        output: &mut [Self; 1024],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Unpacks and delta decodes 1024 elements in natural order, where `W` is runtime-known
+    /// instead of compile-time known.
+    ///
+    /// # Safety
+    /// The input slice must be of length `1024 * W / T`, where `T` is the bit-width of Self and `W`
+    /// is the packed width. The base slice must be of length `Self::LANES` and the output slice
+    /// must be of exactly length 1024.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_undelta_pack_untransposed(
+        width: usize,
+        input: &[Self],
+        base: &[Self],
+        output: &mut [Self],
+    );
+
+    /// Decodes the single element at `index`. Deltas only accumulate along a lane, so only the
+    /// rows of its own lane up to and including its row are unpacked.
+    ///
+    /// # Panics
+    /// Panics if `index` is not less than 1024.
+    fn undelta_single<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        base: &[Self; Self::LANES],
+        index: usize,
+    ) -> Self
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Decodes the single element at `index`, where `W` is runtime-known instead of compile-time
+    /// known.
+    ///
+    /// # Safety
+    /// The input slice must be of length `1024 * W / T`, where `T` is the bit-width of Self and `W`
+    /// is the packed width. The base slice must be of length `Self::LANES`.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_undelta_single(
+        width: usize,
+        input: &[Self],
+        base: &[Self],
+        index: usize,
+    ) -> Self;
+
+    /// Decodes the elements `start..end` into `output` in index order, see
+    /// `BitPacking::unpack_range`. Every lane touched by the range is only unpacked up to the
+    /// last row it has in the range.
+    ///
+    /// # Panics
+    /// Panics if `start..end` is not within `0..1024`, or `output` is not of length `end - start`.
+    fn undelta_pack_range<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        base: &[Self; Self::LANES],
+        start: usize,
+        end: usize,
+        output: &mut [Self],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Decodes the elements `start..end`, where `W` is runtime-known instead of compile-time
+    /// known.
+    ///
+    /// # Safety
+    /// The input slice must be of length `1024 * W / T`, where `T` is the bit-width of Self and `W`
+    /// is the packed width. The base slice must be of length `Self::LANES`.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_undelta_pack_range(
+        width: usize,
+        input: &[Self],
+        base: &[Self],
+        start: usize,
+        end: usize,
+        output: &mut [Self],
+    );
+
+    /// Decodes the elements selected by `selection`, a mask as written by `Compare`, to the front
+    /// of `output` in ascending index order. Returns the number of selected elements.
+    ///
+    /// Every element depends on all preceding elements of its lane. Sparse selections only decode
+    /// their lanes up to their last selected row, anything denser is compacted while decoding the
+    /// whole vector.
+    fn undelta_pack_selected<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        base: &[Self; Self::LANES],
+        selection: &Mask1024,
+        output: &mut [Self; 1024],
+    ) -> usize
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Delta encodes signed values, zigzag encoding each delta so that decreasing sequences
+    /// pack as narrowly as increasing ones.
+    fn delta_signed(
+        input: &[<Self as ZigZag>::Signed; 1024],
+        base: &[<Self as ZigZag>::Signed; Self::LANES],
+        output: &mut [Self; 1024],
+    ) where
+        Self: ZigZag;
+
+    fn undelta_signed(
+        input: &[Self; 1024],
+        base: &[<Self as ZigZag>::Signed; Self::LANES],
+        output: &mut [<Self as ZigZag>::Signed; 1024],
+    ) where
+        Self: ZigZag;
+
+    fn undelta_pack_signed<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        base: &[<Self as ZigZag>::Signed; Self::LANES],
+        output: &mut [<Self as ZigZag>::Signed; 1024],
+    ) where
+        Self: ZigZag,
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Delta encodes and packs a naturally ordered slice of any length into `width` bits per
+    /// delta, appending to `output`.
+    ///
+    /// Each 1024-element chunk is transposed and the first row of every lane is appended to
+    /// `bases`, i.e. `Self::LANES` bases per chunk. The final partial chunk is padded by repeating
+    /// its last element, so the padding deltas are all zero. Returns the width and logical length
+    /// to decode them with.
+    #[cfg(feature = "alloc")]
+    fn delta_pack_slice(
+        width: usize,
+        input: &[Self],
+        bases: &mut Vec<Self>,
+        output: &mut Vec<Self>,
+    ) -> SliceHeader;
+
+    /// Decodes the `header.len` naturally ordered elements packed by `delta_pack_slice`,
+    /// appending them to `output`.
+    #[cfg(feature = "alloc")]
+    fn undelta_pack_slice(
+        header: SliceHeader,
+        input: &[Self],
+        bases: &[Self],
+        output: &mut Vec<Self>,
+    );
 }
 
 macro_rules! impl_delta {
-    ($T:ty) => {
+    ($T:ty, $S:ty) => {
         paste! {
             impl Delta for $T {
+                fn analyze(input: &[Self; 1024], base: &mut [Self; Self::LANES]) -> usize {
+                    let mut or = [0; Self::LANES];
+                    for lane in 0..Self::LANES {
+                        // The first row of every lane is at index `lane`, see `iterate!`.
+                        base[lane] = input[lane];
+                        let mut prev = base[lane];
+                        iterate!($T, lane, |$idx| {
+                            let next = input[$idx];
+                            or[lane] |= next.wrapping_sub(prev);
+                            prev = next;
+                        });
+                    }
+                    let or = or.iter().fold(0, |acc, &lane| acc | lane);
+                    Self::T - or.leading_zeros() as usize
+                }
+
                 #[inline(never)]
                 fn delta(input: &[Self; 1024], base: &[Self; Self::LANES], output: &mut [Self; 1024]) {
                     for lane in 0..Self::LANES {
@@ -44,6 +284,93 @@ macro_rules! impl_delta {
                     }
                 }
 
+                #[inline(never)]
+                fn delta_pack<const W: usize>(
+                    input: &[Self; 1024],
+                    base: &[Self; Self::LANES],
+                    output: &mut [Self; 1024 * W / Self::T],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    for lane in 0..Self::LANES {
+                        let mut prev = base[lane];
+                        pack!($T, W, output, lane, |$idx| {
+                            let next = input[$idx];
+                            let delta = next.wrapping_sub(prev);
+                            prev = next;
+                            delta
+                        });
+                    }
+                }
+
+                unsafe fn unchecked_delta_pack(width: usize, input: &[Self], base: &[Self], output: &mut [Self]) {
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(output.len(), packed_len, "Output buffer must be of size 1024 * W / T");
+                    debug_assert_eq!(input.len(), 1024, "Input buffer must be of size 1024");
+                    debug_assert_eq!(base.len(), Self::LANES, "Base must be of size {}", Self::LANES);
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => Self::delta_pack::<W>(
+                                array_ref![input, 0, 1024],
+                                array_ref![base, 0, <$T>::LANES],
+                                array_mut_ref![output, 0, 1024 * W / <$T>::T],
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => Self::delta_pack::<{ Self::T }>(
+                                array_ref![input, 0, 1024],
+                                array_ref![base, 0, <$T>::LANES],
+                                array_mut_ref![output, 0, 1024],
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
+                fn delta_pack_untransposed<const W: usize>(
+                    input: &[Self; 1024],
+                    base: &[Self; Self::LANES],
+                    output: &mut [Self; 1024 * W / Self::T],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    for lane in 0..Self::LANES {
+                        let mut prev = base[lane];
+                        pack!($T, W, output, lane, |$idx| {
+                            let next = input[transpose($idx)];
+                            let delta = next.wrapping_sub(prev);
+                            prev = next;
+                            delta
+                        });
+                    }
+                }
+
+                unsafe fn unchecked_delta_pack_untransposed(width: usize, input: &[Self], base: &[Self], output: &mut [Self]) {
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(output.len(), packed_len, "Output buffer must be of size 1024 * W / T");
+                    debug_assert_eq!(input.len(), 1024, "Input buffer must be of size 1024");
+                    debug_assert_eq!(base.len(), Self::LANES, "Base must be of size {}", Self::LANES);
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => Self::delta_pack_untransposed::<W>(
+                                array_ref![input, 0, 1024],
+                                array_ref![base, 0, <$T>::LANES],
+                                array_mut_ref![output, 0, 1024 * W / <$T>::T],
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => Self::delta_pack_untransposed::<{ Self::T }>(
+                                array_ref![input, 0, 1024],
+                                array_ref![base, 0, <$T>::LANES],
+                                array_mut_ref![output, 0, 1024],
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
                 #[inline(never)]
                 fn undelta_pack<const W: usize>(
                     input: &[Self; 1024 * W / Self::T],
@@ -61,15 +388,331 @@ macro_rules! impl_delta {
                         });
                     }
                 }
+
+                unsafe fn unchecked_undelta_pack(width: usize, input: &[Self], base: &[Self], output: &mut [Self]) {
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(input.len(), packed_len, "Input buffer must be of size 1024 * W / T");
+                    debug_assert_eq!(base.len(), Self::LANES, "Base must be of size {}", Self::LANES);
+                    debug_assert_eq!(output.len(), 1024, "Output buffer must be of size 1024");
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => Self::undelta_pack::<W>(
+                                array_ref![input, 0, 1024 * W / <$T>::T],
+                                array_ref![base, 0, <$T>::LANES],
+                                array_mut_ref![output, 0, 1024],
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => Self::undelta_pack::<{ Self::T }>(
+                                array_ref![input, 0, 1024],
+                                array_ref![base, 0, <$T>::LANES],
+                                array_mut_ref![output, 0, 1024],
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
+                fn undelta_pack_untransposed<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    base: &[Self; Self::LANES],
+                    output: &mut [Self; 1024],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    for lane in 0..Self::LANES {
+                        let mut prev = base[lane];
+                        unpack!($T, W, input, lane, |$idx, $elem| {
+                            let next = $elem.wrapping_add(prev);
+                            output[transpose($idx)] = next;
+                            prev = next;
+                        });
+                    }
+                }
+
+                unsafe fn unchecked_undelta_pack_untransposed(width: usize, input: &[Self], base: &[Self], output: &mut [Self]) {
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(input.len(), packed_len, "Input buffer must be of size 1024 * W / T");
+                    debug_assert_eq!(base.len(), Self::LANES, "Base must be of size {}", Self::LANES);
+                    debug_assert_eq!(output.len(), 1024, "Output buffer must be of size 1024");
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => Self::undelta_pack_untransposed::<W>(
+                                array_ref![input, 0, 1024 * W / <$T>::T],
+                                array_ref![base, 0, <$T>::LANES],
+                                array_mut_ref![output, 0, 1024],
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => Self::undelta_pack_untransposed::<{ Self::T }>(
+                                array_ref![input, 0, 1024],
+                                array_ref![base, 0, <$T>::LANES],
+                                array_mut_ref![output, 0, 1024],
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
+                fn undelta_single<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    base: &[Self; Self::LANES],
+                    index: usize,
+                ) -> Self
+                where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    const ROWS: [u8; 1024] = rows_by_index::<$T>();
+                    assert!(index < 1024, "Index must be less than 1024, got {}", index);
+                    let lane = index % Self::LANES;
+
+                    let mut value = base[lane];
+                    crate::[<unpack_rows_ $T>]::<W>(input, lane, prefix_rows(1 << ROWS[index]), |_, delta| {
+                        value = value.wrapping_add(delta);
+                    });
+                    value
+                }
+
+                unsafe fn unchecked_undelta_single(width: usize, input: &[Self], base: &[Self], index: usize) -> Self {
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(input.len(), packed_len, "Input buffer must be of size 1024 * W / T");
+                    debug_assert_eq!(base.len(), Self::LANES, "Base must be of size {}", Self::LANES);
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => Self::undelta_single::<W>(
+                                array_ref![input, 0, 1024 * W / <$T>::T],
+                                array_ref![base, 0, <$T>::LANES],
+                                index,
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => Self::undelta_single::<{ Self::T }>(
+                                array_ref![input, 0, 1024],
+                                array_ref![base, 0, <$T>::LANES],
+                                index,
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
+                fn undelta_pack_selected<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    base: &[Self; Self::LANES],
+                    selection: &Mask1024,
+                    output: &mut [Self; 1024],
+                ) -> usize
+                where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    let count = selection.count_ones();
+                    let positions = Positions::new(selection);
+                    if count <= SPARSE_SELECTION {
+                        const ROWS: [u8; 1024] = rows_by_index::<$T>();
+                        // The selected rows of every lane.
+                        let mut indices = [0; SPARSE_SELECTION];
+                        selected_indices(selection, &mut indices);
+                        let mut rows = [0u64; Self::LANES];
+                        for &index in &indices[..count] {
+                            let (index, lane) = (index as usize, index as usize % Self::LANES);
+                            rows[lane] |= 1 << ROWS[index];
+                        }
+                        for lane in 0..Self::LANES {
+                            let mut value = base[lane];
+                            crate::[<unpack_rows_ $T>]::<W>(input, lane, prefix_rows(rows[lane]), |index, delta| {
+                                value = value.wrapping_add(delta);
+                                if let Some(n) = positions.get(index) {
+                                    output[n] = value;
+                                }
+                            });
+                        }
+                        return count;
+                    }
+
+                    for lane in 0..Self::LANES {
+                        let mut prev = base[lane];
+                        unpack!($T, W, input, lane, |$idx, $elem| {
+                            let next = $elem.wrapping_add(prev);
+                            if let Some(n) = positions.get($idx) {
+                                output[n] = next;
+                            }
+                            prev = next;
+                        });
+                    }
+                    count
+                }
+
+                fn undelta_pack_range<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    base: &[Self; Self::LANES],
+                    start: usize,
+                    end: usize,
+                    output: &mut [Self],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    const ROWS: [u8; 1024] = rows_by_index::<$T>();
+                    assert!(start <= end && end <= 1024, "Range {}..{} must be within 0..1024", start, end);
+                    assert_eq!(output.len(), end - start, "Output must be of the same length as the range");
+
+                    let mut rows = [0; Self::LANES];
+                    range_rows(&ROWS, start, end, &mut rows);
+                    for lane in 0..Self::LANES {
+                        // Every row up to the last one in the range contributes to the prefix sum.
+                        let mut value = base[lane];
+                        crate::[<unpack_rows_ $T>]::<W>(input, lane, prefix_rows(rows[lane]), |index, delta| {
+                            value = value.wrapping_add(delta);
+                            if (start..end).contains(&index) {
+                                output[index - start] = value;
+                            }
+                        });
+                    }
+                }
+
+                unsafe fn unchecked_undelta_pack_range(
+                    width: usize,
+                    input: &[Self],
+                    base: &[Self],
+                    start: usize,
+                    end: usize,
+                    output: &mut [Self],
+                ) {
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(input.len(), packed_len, "Input buffer must be of size 1024 * W / T");
+                    debug_assert_eq!(base.len(), Self::LANES, "Base must be of size {}", Self::LANES);
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => Self::undelta_pack_range::<W>(
+                                array_ref![input, 0, 1024 * W / <$T>::T],
+                                array_ref![base, 0, <$T>::LANES],
+                                start,
+                                end,
+                                output,
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => Self::undelta_pack_range::<{ Self::T }>(
+                                array_ref![input, 0, 1024],
+                                array_ref![base, 0, <$T>::LANES],
+                                start,
+                                end,
+                                output,
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
+                #[inline(never)]
+                #[allow(clippy::cast_sign_loss)]
+                fn delta_signed(input: &[$S; 1024], base: &[$S; Self::LANES], output: &mut [Self; 1024]) {
+                    for lane in 0..Self::LANES {
+                        let mut prev = base[lane];
+                        iterate!($T, lane, |$idx| {
+                            let next = input[$idx];
+                            let delta = next.wrapping_sub(prev);
+                            output[$idx] = ((delta << 1) ^ (delta >> (Self::T - 1))) as $T;
+                            prev = next;
+                        });
+                    }
+                }
+
+                #[inline(never)]
+                #[allow(clippy::cast_possible_wrap)]
+                fn undelta_signed(input: &[Self; 1024], base: &[$S; Self::LANES], output: &mut [$S; 1024]) {
+                    for lane in 0..Self::LANES {
+                        let mut prev = base[lane];
+                        iterate!($T, lane, |$idx| {
+                            let delta = ((input[$idx] >> 1) as $S) ^ -((input[$idx] & 1) as $S);
+                            let next = delta.wrapping_add(prev);
+                            output[$idx] = next;
+                            prev = next;
+                        });
+                    }
+                }
+
+                #[inline(never)]
+                #[allow(clippy::cast_possible_wrap)]
+                fn undelta_pack_signed<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    base: &[$S; Self::LANES],
+                    output: &mut [$S; 1024],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    for lane in 0..Self::LANES {
+                        let mut prev = base[lane];
+                        unpack!($T, W, input, lane, |$idx, $elem| {
+                            let delta = (($elem >> 1) as $S) ^ -(($elem & 1) as $S);
+                            let next = delta.wrapping_add(prev);
+                            output[$idx] = next;
+                            prev = next;
+                        });
+                    }
+                }
+
+                #[cfg(feature = "alloc")]
+                fn delta_pack_slice(width: usize, input: &[Self], bases: &mut Vec<Self>, output: &mut Vec<Self>) -> SliceHeader {
+                    assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+                    let packed_len = 128 * width / size_of::<Self>();
+                    bases.reserve(input.len().div_ceil(1024) * Self::LANES);
+                    output.reserve(input.len().div_ceil(1024) * packed_len);
+
+                    for chunk in input.chunks(1024) {
+                        let mut padded = [chunk[chunk.len() - 1]; 1024];
+                        padded[..chunk.len()].copy_from_slice(chunk);
+
+                        let base: [Self; Self::LANES] = core::array::from_fn(|lane| padded[transpose(lane)]);
+                        bases.extend_from_slice(&base);
+
+                        let start = output.len();
+                        output.resize(start + packed_len, 0);
+                        unsafe { Self::unchecked_delta_pack_untransposed(width, &padded, &base, &mut output[start..]) };
+                    }
+                    SliceHeader { width, len: input.len() }
+                }
+
+                #[cfg(feature = "alloc")]
+                fn undelta_pack_slice(
+                    header: SliceHeader,
+                    input: &[Self],
+                    bases: &[Self],
+                    output: &mut Vec<Self>,
+                ) {
+                    let SliceHeader { width, len } = header;
+                    assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+                    let packed_len = 128 * width / size_of::<Self>();
+                    let chunks = len.div_ceil(1024);
+                    assert_eq!(input.len(), chunks * packed_len, "Input buffer must hold {} packed chunks", chunks);
+                    assert_eq!(bases.len(), chunks * Self::LANES, "Bases must hold {} lanes per chunk", Self::LANES);
+                    output.reserve(len);
+
+                    for i in 0..chunks {
+                        let mut unpacked = [0; 1024];
+                        unsafe {
+                            Self::unchecked_undelta_pack_untransposed(
+                                width,
+                                &input[i * packed_len..][..packed_len],
+                                &bases[i * Self::LANES..][..Self::LANES],
+                                &mut unpacked,
+                            )
+                        };
+                        output.extend_from_slice(&unpacked[..(len - i * 1024).min(1024)]);
+                    }
+                }
             }
         }
     };
 }
 
-impl_delta!(u8);
-impl_delta!(u16);
-impl_delta!(u32);
-impl_delta!(u64);
+impl_delta!(u8, i8);
+impl_delta!(u16, i16);
+impl_delta!(u32, i32);
+impl_delta!(u64, i64);
 
 #[cfg(test)]
 mod test {
@@ -105,4 +748,240 @@ mod test {
         Delta::undelta(&unpacked, &[0; 64], &mut undelta);
         assert_eq!(transposed, undelta);
     }
+
+    #[test]
+    fn test_delta_pack() {
+        const W: usize = 15;
+        let values: [u16; 1024] = core::array::from_fn(|i| (i / 8) as u16);
+        let mut transposed = [0; 1024];
+        Transpose::transpose(&values, &mut transposed);
+
+        // Unfused encode
+        let mut deltas = [0; 1024];
+        Delta::delta(&transposed, &[0; 64], &mut deltas);
+        let mut packed = [0; 128 * W / size_of::<u16>()];
+        BitPacking::pack::<W>(&deltas, &mut packed);
+
+        // Fused encode
+        let mut fused = [0; 128 * W / size_of::<u16>()];
+        Delta::delta_pack::<W>(&transposed, &[0; 64], &mut fused);
+        assert_eq!(packed, fused);
+
+        let mut unchecked = [0; 128 * W / size_of::<u16>()];
+        unsafe { Delta::unchecked_delta_pack(W, &transposed, &[0; 64], &mut unchecked) };
+        assert_eq!(packed, unchecked);
+    }
+
+    #[test]
+    fn test_delta_untransposed() {
+        const W: usize = 6;
+        let values: [u32; 1024] = core::array::from_fn(|i| (i * 3 + i % 2) as u32);
+        let mut transposed = [0; 1024];
+        Transpose::transpose(&values, &mut transposed);
+        let base: [u32; 32] = core::array::from_fn(|lane| values[transpose(lane)]);
+        assert_eq!(base[..], transposed[..32]);
+
+        let mut packed = [0; 128 * W / size_of::<u32>()];
+        Delta::delta_pack::<W>(&transposed, &base, &mut packed);
+
+        let mut fused = [0; 128 * W / size_of::<u32>()];
+        Delta::delta_pack_untransposed::<W>(&values, &base, &mut fused);
+        assert_eq!(packed, fused);
+
+        let mut unchecked = [0; 128 * W / size_of::<u32>()];
+        unsafe { Delta::unchecked_delta_pack_untransposed(W, &values, &base, &mut unchecked) };
+        assert_eq!(packed, unchecked);
+
+        let mut unpacked = [0; 1024];
+        Delta::undelta_pack_untransposed::<W>(&packed, &base, &mut unpacked);
+        assert_eq!(values, unpacked);
+
+        let mut unchecked = [0; 1024];
+        unsafe { Delta::unchecked_undelta_pack_untransposed(W, &packed, &base, &mut unchecked) };
+        assert_eq!(values, unchecked);
+    }
+
+    #[test]
+    fn test_analyze() {
+        let values: [u16; 1024] = core::array::from_fn(|i| (1000 + i * 3) as u16);
+        let mut transposed = [0; 1024];
+        Transpose::transpose(&values, &mut transposed);
+
+        let mut base = [0; 64];
+        assert_eq!(Delta::analyze(&transposed, &mut base), 2);
+        assert_eq!(base[..], transposed[..64]);
+
+        let mut deltas = [0; 1024];
+        Delta::delta(&transposed, &base, &mut deltas);
+        assert_eq!(BitPacking::min_width(&deltas), 2);
+
+        assert_eq!(Delta::analyze(&[7u64; 1024], &mut [0; 16]), 0);
+    }
+
+    #[test]
+    fn test_unchecked_undelta_pack() {
+        const W: usize = 4;
+        let values: [u32; 1024] = core::array::from_fn(|i| (i * 5) as u32);
+        let mut transposed = [0; 1024];
+        Transpose::transpose(&values, &mut transposed);
+
+        let base: [u32; 32] = *arrayref::array_ref![transposed, 0, 32];
+        let mut deltas = [0; 1024];
+        Delta::delta(&transposed, &base, &mut deltas);
+        let mut packed = [0; 128 * W / size_of::<u32>()];
+        BitPacking::pack::<W>(&deltas, &mut packed);
+
+        let mut unpacked = [0; 1024];
+        unsafe { Delta::unchecked_undelta_pack(W, &packed, &base, &mut unpacked) };
+        assert_eq!(transposed, unpacked);
+    }
+
+    fn try_undelta_single<T: Delta + core::fmt::Debug>() {
+        // Arbitrary packed words and bases, so every lane wraps around at some width.
+        let words: [T; 1024] = core::array::from_fn(|i| T::from(i * 173 % 251).unwrap());
+        let base: [T; 128] = core::array::from_fn(|i| T::from(i * 59 % 241).unwrap());
+        for width in 0..=T::T {
+            let packed = &words[..1024 * width / T::T];
+            let mut unpacked = [T::zero(); 1024];
+            unsafe { T::unchecked_undelta_pack(width, packed, &base[..T::LANES], &mut unpacked) };
+            for (i, &value) in unpacked.iter().enumerate() {
+                let single =
+                    unsafe { T::unchecked_undelta_single(width, packed, &base[..T::LANES], i) };
+                assert_eq!(single, value, "width {width}, index {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_undelta_single() {
+        const W: usize = 3;
+        try_undelta_single::<u8>();
+        try_undelta_single::<u16>();
+        try_undelta_single::<u32>();
+        try_undelta_single::<u64>();
+
+        let values: [u32; 1024] = core::array::from_fn(|i| (1000 + i * 5) as u32);
+        let mut transposed = [0; 1024];
+        Transpose::transpose(&values, &mut transposed);
+        let base = *arrayref::array_ref![transposed, 0, 32];
+        let mut packed = [0; 128 * W / size_of::<u32>()];
+        Delta::delta_pack::<W>(&transposed, &base, &mut packed);
+        for i in 0..1024 {
+            assert_eq!(Delta::undelta_single::<W>(&packed, &base, i), transposed[i]);
+        }
+    }
+
+    #[test]
+    fn test_undelta_pack_range() {
+        const W: usize = 3;
+        let values: [u64; 1024] = core::array::from_fn(|i| (i * 6) as u64);
+        let mut transposed = [0; 1024];
+        Transpose::transpose(&values, &mut transposed);
+        let base = *arrayref::array_ref![transposed, 0, 16];
+        let mut packed = [0; 128 * W / size_of::<u64>()];
+        Delta::delta_pack::<W>(&transposed, &base, &mut packed);
+
+        for (start, end) in [(0, 0), (0, 1024), (17, 18), (300, 900), (1000, 1024)] {
+            let mut output = [0; 1024];
+            Delta::undelta_pack_range::<W>(&packed, &base, start, end, &mut output[..end - start]);
+            assert_eq!(output[..end - start], transposed[start..end]);
+
+            let mut unchecked = [0; 1024];
+            unsafe {
+                Delta::unchecked_undelta_pack_range(
+                    W,
+                    &packed,
+                    &base,
+                    start,
+                    end,
+                    &mut unchecked[..end - start],
+                );
+            };
+            assert_eq!(output, unchecked);
+        }
+    }
+
+    #[test]
+    fn test_undelta_pack_selected() {
+        const W: usize = 2;
+        let values: [u16; 1024] = core::array::from_fn(|i| (i * 3) as u16);
+        let mut transposed = [0; 1024];
+        Transpose::transpose(&values, &mut transposed);
+        let base = *arrayref::array_ref![transposed, 0, 64];
+        let mut packed = [0; 128 * W / size_of::<u16>()];
+        Delta::delta_pack::<W>(&transposed, &base, &mut packed);
+
+        // Select every element whose delta is non-zero, i.e. all but the first row of each lane.
+        let mut selection = Mask1024::NONE;
+        crate::Compare::gt::<W>(&packed, 0, 0, &mut selection);
+        let mut output = [0; 1024];
+        let n = Delta::undelta_pack_selected::<W>(&packed, &base, &selection, &mut output);
+        assert_eq!(n, 1024 - 64);
+        assert!(output[..n]
+            .iter()
+            .eq(transposed.iter().filter(|&v| !base.contains(v))));
+
+        // A sparse selection only decodes the lanes it touches up to its last row.
+        let mut selection = Mask1024::NONE;
+        for i in [5, 130, 700, 1023] {
+            selection.set(i, true);
+        }
+        let n = Delta::undelta_pack_selected::<W>(&packed, &base, &selection, &mut output);
+        assert_eq!(output[..n], [5, 130, 700, 1023].map(|i| transposed[i]));
+    }
+
+    #[test]
+    fn test_delta_signed() {
+        const W: usize = 4;
+        // A decreasing sequence with a sawtooth, so deltas are of both signs.
+        let mut values: [i32; 1024] = [0; 1024];
+        for i in 0..1024 {
+            values[i] = -i32::try_from(i).unwrap() + i32::try_from(i % 4).unwrap();
+        }
+
+        let mut transposed = [0; 1024];
+        Transpose::transpose(&values, &mut transposed);
+
+        // The first row of each lane serves as its base, see `iterate!`.
+        let base: [i32; 32] = *arrayref::array_ref![transposed, 0, 32];
+        let mut deltas = [0u32; 1024];
+        u32::delta_signed(&transposed, &base, &mut deltas);
+        assert!(deltas.iter().all(|&d| d < 1 << W));
+
+        let mut packed = [0; 128 * W / size_of::<u32>()];
+        BitPacking::pack::<W>(&deltas, &mut packed);
+
+        // Fused kernel
+        let mut unpacked = [0i32; 1024];
+        u32::undelta_pack_signed::<W>(&packed, &base, &mut unpacked);
+        assert_eq!(transposed, unpacked);
+
+        // Unfused kernel
+        let mut undelta = [0i32; 1024];
+        u32::undelta_signed(&deltas, &base, &mut undelta);
+        assert_eq!(transposed, undelta);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_delta_slice() {
+        const W: usize = 2;
+        for len in [0usize, 1, 1024, 1500, 4096] {
+            let values: Vec<u16> = (0..len).map(|i| (i * 3) as u16).collect();
+
+            let mut bases = Vec::new();
+            let mut packed = Vec::new();
+            let header = Delta::delta_pack_slice(W, &values, &mut bases, &mut packed);
+            assert_eq!(header, SliceHeader { width: W, len });
+            assert_eq!(bases.len(), len.div_ceil(1024) * 64);
+            assert_eq!(
+                packed.len(),
+                len.div_ceil(1024) * 128 * W / size_of::<u16>()
+            );
+
+            let mut unpacked = Vec::new();
+            Delta::undelta_pack_slice(header, &packed, &bases, &mut unpacked);
+            assert_eq!(values, unpacked);
+        }
+    }
 }
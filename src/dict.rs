@@ -0,0 +1,208 @@
+use arrayref::{array_mut_ref, array_ref};
+use core::mem::size_of;
+
+use crate::{seq_t, unpack, BitPackWidth, BitPacking, FastLanes, SupportedBitPackWidth};
+use paste::paste;
+
+/// Dictionary encoding of low-cardinality vectors.
+///
+/// A vector is split into its sorted distinct values and, for every element, the `u16` code of
+/// its value. The codes fit into `W = ceil(log2(n))` bits for `n` distinct values and decoding
+/// becomes a fused unpack + lookup.
+pub trait Dictionary: BitPacking {
+    /// Encodes a vector into its sorted distinct values and per-element codes.
+    /// Returns the number of values written to `values`.
+    fn dict(input: &[Self; 1024], values: &mut [Self; 1024], codes: &mut [u16; 1024]) -> usize;
+
+    /// Dictionary encodes a vector and packs its codes into the smallest width `W` that fits
+    /// them, see `BitPacking::min_width`. Returns the number of values written to `values` and
+    /// `W`, the packed codes are the first `1024 * W / 16` elements of `packed`.
+    fn dict_pack(
+        input: &[Self; 1024],
+        values: &mut [Self; 1024],
+        packed: &mut [u16; 1024],
+    ) -> (usize, usize);
+
+    fn undict(values: &[Self], codes: &[u16; 1024], output: &mut [Self; 1024]);
+
+    /// Decodes `W`-bit packed codes directly into their values.
+    fn undict_pack<const W: usize>(
+        input: &[u16; 1024 * W / u16::T],
+        values: &[Self],
+        output: &mut [Self; 1024],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<u16>;
+
+    /// Decodes codes packed into a runtime-known width `W`, see `undict_pack`.
+    ///
+    /// # Safety
+    /// The input slice must be of length `1024 * W / 16`, where `W` is the packed width. The
+    /// output slice must be of exactly length 1024.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_undict_pack(
+        width: usize,
+        input: &[u16],
+        values: &[Self],
+        output: &mut [Self],
+    );
+}
+
+macro_rules! impl_dict {
+    ($T:ty) => {
+        paste! {
+            impl Dictionary for $T {
+                fn dict(input: &[Self; 1024], values: &mut [Self; 1024], codes: &mut [u16; 1024]) -> usize {
+                    values.copy_from_slice(input);
+                    values.sort_unstable();
+                    let mut n = 1;
+                    for i in 1..1024 {
+                        values[n] = values[i];
+                        n += usize::from(values[i] != values[n - 1]);
+                    }
+
+                    for i in 0..1024 {
+                        let Ok(code) = values[..n].binary_search(&input[i]) else {
+                            unreachable!("Every input value is in the dictionary")
+                        };
+                        codes[i] = code as u16;
+                    }
+                    n
+                }
+
+                fn dict_pack(input: &[Self; 1024], values: &mut [Self; 1024], packed: &mut [u16; 1024]) -> (usize, usize) {
+                    let mut codes = [0; 1024];
+                    let n = Self::dict(input, values, &mut codes);
+                    let width = u16::min_width(&codes);
+                    let packed_len = 128 * width / size_of::<u16>();
+                    unsafe { BitPacking::unchecked_pack(width, &codes, &mut packed[..packed_len]) };
+                    (n, width)
+                }
+
+                fn undict(values: &[Self], codes: &[u16; 1024], output: &mut [Self; 1024]) {
+                    for i in 0..1024 {
+                        output[i] = values[codes[i] as usize];
+                    }
+                }
+
+                #[inline(never)]
+                fn undict_pack<const W: usize>(
+                    input: &[u16; 1024 * W / u16::T],
+                    values: &[Self],
+                    output: &mut [Self; 1024],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<u16>,
+                {
+                    for lane in 0..u16::LANES {
+                        unpack!(u16, W, input, lane, |$idx, $elem| {
+                            output[$idx] = values[$elem as usize]
+                        });
+                    }
+                }
+
+                unsafe fn unchecked_undict_pack(width: usize, input: &[u16], values: &[Self], output: &mut [Self]) {
+                    let packed_len = 128 * width / size_of::<u16>();
+                    debug_assert_eq!(input.len(), packed_len, "Input buffer must be of size 1024 * W / 16");
+                    debug_assert_eq!(output.len(), 1024, "Output buffer must be of size 1024");
+                    debug_assert!(width <= u16::T, "Width must be less than or equal to {}", u16::T);
+
+                    seq_t!(W in u16 {
+                        match width {
+                            #(W => Self::undict_pack::<W>(
+                                array_ref![input, 0, 1024 * W / u16::T],
+                                values,
+                                array_mut_ref![output, 0, 1024],
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            16 => Self::undict_pack::<16>(
+                                array_ref![input, 0, 1024],
+                                values,
+                                array_mut_ref![output, 0, 1024],
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+            }
+        }
+    };
+}
+
+impl_dict!(u8);
+impl_dict!(u16);
+impl_dict!(u32);
+impl_dict!(u64);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::fmt::Debug;
+
+    fn try_dict<T: Dictionary + Debug>() {
+        let mut values = [T::zero(); 1024];
+        for i in 0..1024 {
+            values[i] = T::from((i * 7) % 5 * 40 + 3).unwrap();
+        }
+
+        let mut dictionary = [T::zero(); 1024];
+        let mut codes = [0u16; 1024];
+        let n = Dictionary::dict(&values, &mut dictionary, &mut codes);
+        assert_eq!(n, 5);
+        assert!(dictionary[..n].windows(2).all(|w| w[0] < w[1]));
+
+        let mut undict = [T::zero(); 1024];
+        Dictionary::undict(&dictionary[..n], &codes, &mut undict);
+        assert_eq!(values, undict);
+
+        let mut packed = [0u16; 128 * 3 / size_of::<u16>()];
+        BitPacking::pack::<3>(&codes, &mut packed);
+
+        let mut unpacked = [T::zero(); 1024];
+        Dictionary::undict_pack::<3>(&packed, &dictionary[..n], &mut unpacked);
+        assert_eq!(values, unpacked);
+
+        let mut unchecked = [T::zero(); 1024];
+        unsafe { Dictionary::unchecked_undict_pack(3, &packed, &dictionary[..n], &mut unchecked) };
+        assert_eq!(values, unchecked);
+
+        let mut packed = [0u16; 1024];
+        let (n, width) = Dictionary::dict_pack(&values, &mut dictionary, &mut packed);
+        assert_eq!((n, width), (5, 3));
+        let packed_len = 128 * width / size_of::<u16>();
+        let mut unchecked = [T::zero(); 1024];
+        unsafe {
+            Dictionary::unchecked_undict_pack(
+                width,
+                &packed[..packed_len],
+                &dictionary[..n],
+                &mut unchecked,
+            );
+        };
+        assert_eq!(values, unchecked);
+    }
+
+    #[test]
+    fn test_dict() {
+        try_dict::<u8>();
+        try_dict::<u16>();
+        try_dict::<u32>();
+        try_dict::<u64>();
+    }
+
+    #[test]
+    fn test_dict_constant() {
+        let values = [42u32; 1024];
+        let mut dictionary = [0; 1024];
+        let mut codes = [0u16; 1024];
+        assert_eq!(Dictionary::dict(&values, &mut dictionary, &mut codes), 1);
+
+        let mut packed = [0u16; 1024];
+        assert_eq!(
+            Dictionary::dict_pack(&values, &mut dictionary, &mut packed),
+            (1, 0)
+        );
+
+        let mut unpacked = [0; 1024];
+        Dictionary::undict_pack::<0>(&[], &dictionary[..1], &mut unpacked);
+        assert_eq!(values, unpacked);
+    }
+}
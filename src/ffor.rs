@@ -1,7 +1,22 @@
-use crate::{pack, unpack, BitPackWidth, BitPacking, FastLanes, SupportedBitPackWidth};
+#[cfg(feature = "alloc")]
+use crate::SliceHeader;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use arrayref::{array_mut_ref, array_ref};
+use core::mem::size_of;
+
+use crate::util::compact_with;
+use crate::{
+    pack, range_rows, rows_by_index, selected_indices, seq_t, unpack, BitPackWidth, BitPacking,
+    FastLanes, Mask1024, Positions, SupportedBitPackWidth, ZigZag, SPARSE_SELECTION,
+};
 use paste::paste;
 
 pub trait FoR: BitPacking {
+    /// Returns the reference (the minimum element) and the bit-width `W` needed to pack all
+    /// 1024 elements relative to it.
+    fn analyze(input: &[Self; 1024]) -> (Self, usize);
+
     fn for_pack<const W: usize>(
         input: &[Self; 1024],
         reference: Self,
@@ -9,18 +24,159 @@ pub trait FoR: BitPacking {
     ) where
         BitPackWidth<W>: SupportedBitPackWidth<Self>;
 
+    /// Frame-of-reference packs 1024 elements into `W` bits each, where `W` is runtime-known
+    /// instead of compile-time known.
+    ///
+    /// # Safety
+    /// The input slice must be of exactly length 1024. The output slice must be of length
+    /// `1024 * W / T`, where `T` is the bit-width of Self and `W` is the packed width.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_for_pack(
+        width: usize,
+        input: &[Self],
+        reference: Self,
+        output: &mut [Self],
+    );
+
     fn unfor_pack<const W: usize>(
         input: &[Self; 1024 * W / Self::T],
         reference: Self,
         output: &mut [Self; 1024],
     ) where
         BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Unpacks 1024 frame-of-reference elements from `W` bits each, where `W` is runtime-known
+    /// instead of compile-time known.
+    ///
+    /// # Safety
+    /// The input slice must be of length `1024 * W / T`, where `T` is the bit-width of Self and `W`
+    /// is the packed width. The output slice must be of exactly length 1024.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_unfor_pack(
+        width: usize,
+        input: &[Self],
+        reference: Self,
+        output: &mut [Self],
+    );
+
+    /// Patched frame-of-reference (PFOR). Values whose offset from `reference` does not fit into
+    /// `W` bits are recorded as (index, value) exceptions instead of being silently truncated.
+    /// Returns the number of exceptions written.
+    fn for_pack_patched<const W: usize>(
+        input: &[Self; 1024],
+        reference: Self,
+        output: &mut [Self; 1024 * W / Self::T],
+        exception_indices: &mut [u16; 1024],
+        exception_values: &mut [Self; 1024],
+    ) -> usize
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    fn unfor_pack_patched<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: Self,
+        exception_indices: &[u16],
+        exception_values: &[Self],
+        output: &mut [Self; 1024],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Unpacks the elements selected by `selection` to the front of `output`, see
+    /// `BitPacking::unpack_selected`. Returns the number of selected elements.
+    fn unfor_pack_selected<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: Self,
+        selection: &Mask1024,
+        output: &mut [Self; 1024],
+    ) -> usize
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Unpacks the elements `start..end` into `output` in index order, see
+    /// `BitPacking::unpack_range`.
+    fn unfor_pack_range<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: Self,
+        start: usize,
+        end: usize,
+        output: &mut [Self],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Unpacks the elements `start..end`, where `W` is runtime-known instead of compile-time
+    /// known.
+    ///
+    /// # Safety
+    /// The input slice must be of length `1024 * W / T`, where `T` is the bit-width of Self and `W`
+    /// is the packed width.
+    /// This length is checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_unfor_pack_range(
+        width: usize,
+        input: &[Self],
+        reference: Self,
+        start: usize,
+        end: usize,
+        output: &mut [Self],
+    );
+
+    /// Frame-of-reference packs signed values. Offsets from a `reference` no greater than any
+    /// input value are non-negative, so they are packed as-is without a zigzag step.
+    fn for_pack_signed<const W: usize>(
+        input: &[<Self as ZigZag>::Signed; 1024],
+        reference: <Self as ZigZag>::Signed,
+        output: &mut [Self; 1024 * W / Self::T],
+    ) where
+        Self: ZigZag,
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    fn unfor_pack_signed<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: <Self as ZigZag>::Signed,
+        output: &mut [<Self as ZigZag>::Signed; 1024],
+    ) where
+        Self: ZigZag,
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Frame-of-reference packs a slice of any length into `width` bits per element, appending
+    /// to `output`. The final partial chunk is padded with `reference`, and the width and logical
+    /// length are returned, see `BitPacking::pack_slice`.
+    #[cfg(feature = "alloc")]
+    fn for_pack_slice(
+        width: usize,
+        input: &[Self],
+        reference: Self,
+        output: &mut Vec<Self>,
+    ) -> SliceHeader;
+
+    /// Unpacks the `header.len` elements packed by `for_pack_slice`, appending them to `output`.
+    #[cfg(feature = "alloc")]
+    fn unfor_pack_slice(
+        header: SliceHeader,
+        input: &[Self],
+        reference: Self,
+        output: &mut Vec<Self>,
+    );
 }
 
 macro_rules! impl_for {
-    ($T:ty) => {
+    ($T:ty, $S:ty) => {
         paste! {
             impl FoR for $T {
+                fn analyze(input: &[Self; 1024]) -> (Self, usize) {
+                    let mut min = [<$T>::MAX; Self::LANES];
+                    let mut max = [<$T>::MIN; Self::LANES];
+                    for row in 0..Self::T {
+                        for lane in 0..Self::LANES {
+                            let value = input[Self::LANES * row + lane];
+                            min[lane] = min[lane].min(value);
+                            max[lane] = max[lane].max(value);
+                        }
+                    }
+                    let min = min.iter().fold(<$T>::MAX, |acc, &lane| acc.min(lane));
+                    let max = max.iter().fold(<$T>::MIN, |acc, &lane| acc.max(lane));
+                    (min, Self::T - (max - min).leading_zeros() as usize)
+                }
+
                 fn for_pack<const W: usize>(
                     input: &[Self; 1024],
                     reference: Self,
@@ -35,6 +191,30 @@ macro_rules! impl_for {
                     }
                 }
 
+                unsafe fn unchecked_for_pack(width: usize, input: &[Self], reference: Self, output: &mut [Self]) {
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(output.len(), packed_len, "Output buffer must be of size 1024 * W / T");
+                    debug_assert_eq!(input.len(), 1024, "Input buffer must be of size 1024");
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => Self::for_pack::<W>(
+                                array_ref![input, 0, 1024],
+                                reference,
+                                array_mut_ref![output, 0, 1024 * W / <$T>::T],
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => Self::for_pack::<{ Self::T }>(
+                                array_ref![input, 0, 1024],
+                                reference,
+                                array_mut_ref![output, 0, 1024],
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
                 fn unfor_pack<const W: usize>(
                     input: &[Self; 1024 * W / Self::T],
                     reference: Self,
@@ -48,15 +228,231 @@ macro_rules! impl_for {
                         });
                     }
                 }
+
+                unsafe fn unchecked_unfor_pack(width: usize, input: &[Self], reference: Self, output: &mut [Self]) {
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(input.len(), packed_len, "Input buffer must be of size 1024 * W / T");
+                    debug_assert_eq!(output.len(), 1024, "Output buffer must be of size 1024");
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => Self::unfor_pack::<W>(
+                                array_ref![input, 0, 1024 * W / <$T>::T],
+                                reference,
+                                array_mut_ref![output, 0, 1024],
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => Self::unfor_pack::<{ Self::T }>(
+                                array_ref![input, 0, 1024],
+                                reference,
+                                array_mut_ref![output, 0, 1024],
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
+                fn for_pack_patched<const W: usize>(
+                    input: &[Self; 1024],
+                    reference: Self,
+                    output: &mut [Self; 1024 * W / Self::T],
+                    exception_indices: &mut [u16; 1024],
+                    exception_values: &mut [Self; 1024],
+                ) -> usize
+                where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    let max: $T = if W == Self::T { <$T>::MAX } else { (1 << W) - 1 };
+
+                    let n = compact_with(|n, i| {
+                        exception_indices[n] = i as u16;
+                        exception_values[n] = input[i];
+                        input[i].wrapping_sub(reference) > max
+                    });
+
+                    Self::for_pack::<W>(input, reference, output);
+                    n
+                }
+
+                fn unfor_pack_patched<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    reference: Self,
+                    exception_indices: &[u16],
+                    exception_values: &[Self],
+                    output: &mut [Self; 1024],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    debug_assert_eq!(exception_indices.len(), exception_values.len());
+                    Self::unfor_pack::<W>(input, reference, output);
+                    for (&idx, &value) in exception_indices.iter().zip(exception_values) {
+                        output[idx as usize] = value;
+                    }
+                }
+
+                fn unfor_pack_selected<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    reference: Self,
+                    selection: &Mask1024,
+                    output: &mut [Self; 1024],
+                ) -> usize
+                where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    let count = selection.count_ones();
+                    if count <= SPARSE_SELECTION {
+                        let mut indices = [0; SPARSE_SELECTION];
+                        selected_indices(selection, &mut indices);
+                        for (i, &index) in indices[..count].iter().enumerate() {
+                            output[i] = Self::unpack_single::<W>(input, index as usize).wrapping_add(reference);
+                        }
+                        return count;
+                    }
+
+                    let positions = Positions::new(selection);
+                    for lane in 0..Self::LANES {
+                        unpack!($T, W, input, lane, |$idx, $elem| {
+                            if let Some(n) = positions.get($idx) {
+                                output[n] = $elem.wrapping_add(reference);
+                            }
+                        });
+                    }
+                    count
+                }
+
+                fn unfor_pack_range<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    reference: Self,
+                    start: usize,
+                    end: usize,
+                    output: &mut [Self],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    const ROWS: [u8; 1024] = rows_by_index::<$T>();
+                    assert!(start <= end && end <= 1024, "Range {}..{} must be within 0..1024", start, end);
+                    assert_eq!(output.len(), end - start, "Output must be of the same length as the range");
+                    let mut rows = [0; Self::LANES];
+                    range_rows(&ROWS, start, end, &mut rows);
+                    for lane in 0..Self::LANES {
+                        crate::[<unpack_rows_ $T>]::<W>(input, lane, rows[lane], |index, elem| {
+                            output[index - start] = elem.wrapping_add(reference);
+                        });
+                    }
+                }
+
+                unsafe fn unchecked_unfor_pack_range(
+                    width: usize,
+                    input: &[Self],
+                    reference: Self,
+                    start: usize,
+                    end: usize,
+                    output: &mut [Self],
+                ) {
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(input.len(), packed_len, "Input buffer must be of size 1024 * W / T");
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => Self::unfor_pack_range::<W>(
+                                array_ref![input, 0, 1024 * W / <$T>::T],
+                                reference,
+                                start,
+                                end,
+                                output,
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => Self::unfor_pack_range::<{ Self::T }>(
+                                array_ref![input, 0, 1024],
+                                reference,
+                                start,
+                                end,
+                                output,
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
+                #[allow(clippy::cast_sign_loss)]
+                fn for_pack_signed<const W: usize>(
+                    input: &[$S; 1024],
+                    reference: $S,
+                    output: &mut [Self; 1024 * W / Self::T],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    for lane in 0..Self::LANES {
+                        pack!($T, W, output, lane, |$idx| {
+                            input[$idx].wrapping_sub(reference) as $T
+                        });
+                    }
+                }
+
+                #[allow(clippy::cast_possible_wrap)]
+                fn unfor_pack_signed<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    reference: $S,
+                    output: &mut [$S; 1024],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    for lane in 0..Self::LANES {
+                        unpack!($T, W, input, lane, |$idx, $elem| {
+                            output[$idx] = ($elem as $S).wrapping_add(reference)
+                        });
+                    }
+                }
+
+                #[cfg(feature = "alloc")]
+                fn for_pack_slice(width: usize, input: &[Self], reference: Self, output: &mut Vec<Self>) -> SliceHeader {
+                    assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+                    let packed_len = 128 * width / size_of::<Self>();
+                    output.reserve(input.len().div_ceil(1024) * packed_len);
+
+                    for chunk in input.chunks(1024) {
+                        // Padding with the reference keeps the tail at a zero offset.
+                        let mut padded = [reference; 1024];
+                        padded[..chunk.len()].copy_from_slice(chunk);
+                        let start = output.len();
+                        output.resize(start + packed_len, 0);
+                        unsafe { Self::unchecked_for_pack(width, &padded, reference, &mut output[start..]) };
+                    }
+                    SliceHeader { width, len: input.len() }
+                }
+
+                #[cfg(feature = "alloc")]
+                fn unfor_pack_slice(
+                    header: SliceHeader,
+                    input: &[Self],
+                    reference: Self,
+                    output: &mut Vec<Self>,
+                ) {
+                    let SliceHeader { width, len } = header;
+                    assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+                    let packed_len = 128 * width / size_of::<Self>();
+                    assert_eq!(input.len(), len.div_ceil(1024) * packed_len, "Input buffer must hold {} packed chunks", len.div_ceil(1024));
+                    output.reserve(len);
+
+                    for i in 0..len.div_ceil(1024) {
+                        let mut unpacked = [0; 1024];
+                        unsafe {
+                            Self::unchecked_unfor_pack(width, &input[i * packed_len..][..packed_len], reference, &mut unpacked)
+                        };
+                        output.extend_from_slice(&unpacked[..(len - i * 1024).min(1024)]);
+                    }
+                }
             }
         }
     };
 }
 
-impl_for!(u8);
-impl_for!(u16);
-impl_for!(u32);
-impl_for!(u64);
+impl_for!(u8, i8);
+impl_for!(u16, i16);
+impl_for!(u32, i32);
+impl_for!(u64, i64);
 
 #[cfg(test)]
 mod test {
@@ -86,4 +482,140 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_analyze() {
+        let mut values = [500u32; 1024];
+        assert_eq!(FoR::analyze(&values), (500, 0));
+
+        values[3] = 1000;
+        values[1000] = 100;
+        assert_eq!(FoR::analyze(&values), (100, 10));
+
+        let (reference, width) = FoR::analyze(&[0, u8::MAX].repeat(512).try_into().unwrap());
+        assert_eq!((reference, width), (0, 8));
+    }
+
+    #[test]
+    fn test_unchecked_ffor() {
+        let input = core::array::from_fn(|i| 1000 + i as u32);
+        let mut packed = [0; 320];
+        unsafe { FoR::unchecked_for_pack(10, &input, 1000, &mut packed) };
+
+        let mut expected = [0; 320];
+        FoR::for_pack::<10>(&input, 1000, &mut expected);
+        assert_eq!(packed, expected);
+
+        let mut output = [0; 1024];
+        unsafe { FoR::unchecked_unfor_pack(10, &packed, 1000, &mut output) };
+        assert_eq!(input, output);
+    }
+
+    #[test]
+    fn test_ffor_patched() {
+        const W: usize = 4;
+        let mut values: [u32; 1024] = [0; 1024];
+        for i in 0..1024 {
+            values[i] = 100 + (i % 16) as u32;
+        }
+        // Sprinkle in outliers that do not fit into W bits, including one below the reference.
+        values[3] = 1 << 30;
+        values[500] = 5000;
+        values[1023] = 99;
+
+        let mut packed = [0; 128 * W / size_of::<u32>()];
+        let mut exception_indices = [0; 1024];
+        let mut exception_values = [0; 1024];
+        let n = FoR::for_pack_patched::<W>(
+            &values,
+            100,
+            &mut packed,
+            &mut exception_indices,
+            &mut exception_values,
+        );
+        assert_eq!(n, 3);
+        assert_eq!(exception_indices[..n], [3, 500, 1023]);
+        assert_eq!(exception_values[..n], [1 << 30, 5000, 99]);
+
+        let mut unpacked = [0; 1024];
+        FoR::unfor_pack_patched::<W>(
+            &packed,
+            100,
+            &exception_indices[..n],
+            &exception_values[..n],
+            &mut unpacked,
+        );
+        assert_eq!(values, unpacked);
+    }
+
+    #[test]
+    fn test_unfor_pack_selected() {
+        const W: usize = 10;
+        let values: [u64; 1024] = core::array::from_fn(|i| 5000 + (i * 7 % 1000) as u64);
+        let mut packed = [0; 128 * W / size_of::<u64>()];
+        FoR::for_pack::<W>(&values, 5000, &mut packed);
+
+        let mut selection = Mask1024::NONE;
+        crate::Compare::ge::<W>(&packed, 5000, 5500, &mut selection);
+        let mut output = [0; 1024];
+        let n = FoR::unfor_pack_selected::<W>(&packed, 5000, &selection, &mut output);
+        assert!(output[..n].iter().eq(values.iter().filter(|&&v| v >= 5500)));
+
+        crate::Compare::eq::<W>(&packed, 5000, 5007, &mut selection);
+        let n = FoR::unfor_pack_selected::<W>(&packed, 5000, &selection, &mut output);
+        assert!(output[..n].iter().eq(values.iter().filter(|&&v| v == 5007)));
+    }
+
+    #[test]
+    fn test_unfor_pack_range() {
+        const W: usize = 10;
+        let values: [u64; 1024] = core::array::from_fn(|i| 5000 + (i * 7 % 1000) as u64);
+        let mut packed = [0; 128 * W / size_of::<u64>()];
+        FoR::for_pack::<W>(&values, 5000, &mut packed);
+
+        let mut output = [0; 300];
+        FoR::unfor_pack_range::<W>(&packed, 5000, 500, 800, &mut output);
+        assert_eq!(output, values[500..800]);
+
+        let mut unchecked = [0; 300];
+        unsafe { FoR::unchecked_unfor_pack_range(W, &packed, 5000, 500, 800, &mut unchecked) };
+        assert_eq!(output, unchecked);
+    }
+
+    #[test]
+    fn test_ffor_signed() {
+        const W: usize = 11;
+        let mut values: [i64; 1024] = [0; 1024];
+        for i in 0..1024 {
+            values[i] = i64::try_from(i).unwrap() - 1_000_000;
+        }
+
+        let mut packed = [0; 128 * W / size_of::<u64>()];
+        u64::for_pack_signed::<W>(&values, -1_000_000, &mut packed);
+
+        let mut unpacked = [0i64; 1024];
+        u64::unfor_pack_signed::<W>(&packed, -1_000_000, &mut unpacked);
+        assert_eq!(values, unpacked);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_ffor_slice() {
+        const W: usize = 10;
+        for len in [0usize, 7, 1024, 2500] {
+            let values: Vec<u64> = (0..len).map(|i| 1 << 40 | (i % 1000) as u64).collect();
+
+            let mut packed = Vec::new();
+            let header = FoR::for_pack_slice(W, &values, 1 << 40, &mut packed);
+            assert_eq!(header, SliceHeader { width: W, len });
+            assert_eq!(
+                packed.len(),
+                len.div_ceil(1024) * 128 * W / size_of::<u64>()
+            );
+
+            let mut unpacked = Vec::new();
+            FoR::unfor_pack_slice(header, &packed, 1 << 40, &mut unpacked);
+            assert_eq!(values, unpacked);
+        }
+    }
 }
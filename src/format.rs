@@ -0,0 +1,290 @@
+//! A self-describing, versioned byte format for [`BitPackedArray`].
+//!
+//! All multi-byte integers are little-endian, regardless of the host. The layout is:
+//!
+//! | offset | size               | field                                               |
+//! |--------|--------------------|-----------------------------------------------------|
+//! | 0      | 4                  | magic, `b"FLNS"`                                    |
+//! | 4      | 1                  | format version, currently 1                         |
+//! | 5      | 1                  | element type, as its bit-width (8, 16, 32 or 64)    |
+//! | 6      | 1                  | encoding: 0 = bit-packed, 1 = `FoR`, 2 = Delta      |
+//! | 7      | 1                  | packed bit-width `W`                                |
+//! | 8      | 8                  | logical length as a `u64`                           |
+//! | 16     | chunks * M * T / 8 | per-chunk metadata: M = 0 (bit-packed), 1 reference |
+//! |        |                    | (`FoR`) or `T::LANES` bases (Delta) per chunk       |
+//! | ...    | chunks * 128 * W   | the packed chunks                                   |
+//!
+//! where `chunks = len.div_ceil(1024)`. The format is not compatible with the C++ `FastLanes`
+//! layout.
+
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use core::mem::size_of;
+
+use num_traits::WrappingAdd;
+
+use crate::{BitPackedArray, Delta, Encoding, FastLanes, FoR};
+
+pub const MAGIC: [u8; 4] = *b"FLNS";
+pub const VERSION: u8 = 1;
+const HEADER_LEN: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WireError {
+    /// The buffer does not start with [`MAGIC`].
+    InvalidMagic([u8; 4]),
+    UnsupportedVersion(u8),
+    /// The element type of the buffer does not match the requested type.
+    ElementTypeMismatch {
+        expected: u8,
+        actual: u8,
+    },
+    InvalidEncoding(u8),
+    /// The packed bit-width exceeds the bit-width of the element type.
+    InvalidWidth(u8),
+    /// The logical length does not fit into a `usize` on this platform.
+    LengthOverflow(u64),
+    /// The buffer is not exactly as long as its header implies.
+    LengthMismatch {
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl Display for WireError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidMagic(magic) => write!(
+                f,
+                "Invalid magic bytes: {:#04x} {:#04x} {:#04x} {:#04x}",
+                magic[0], magic[1], magic[2], magic[3]
+            ),
+            Self::UnsupportedVersion(version) => write!(f, "Unsupported format version: {version}"),
+            Self::ElementTypeMismatch { expected, actual } => {
+                write!(f, "Expected u{expected} elements, found u{actual}")
+            }
+            Self::InvalidEncoding(encoding) => write!(f, "Invalid encoding: {encoding}"),
+            Self::InvalidWidth(width) => write!(f, "Invalid bit-width: {width}"),
+            Self::LengthOverflow(len) => write!(f, "Length {len} does not fit into usize"),
+            Self::LengthMismatch { expected, actual } => {
+                write!(f, "Expected {expected} bytes, found {actual}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for WireError {}
+
+/// Element types that can be written in the wire format.
+pub trait WireType: FastLanes {
+    fn write_le(self, output: &mut Vec<u8>);
+
+    /// Reads a value from exactly `size_of::<Self>()` little-endian bytes.
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_wire_type {
+    ($T:ty) => {
+        impl WireType for $T {
+            fn write_le(self, output: &mut Vec<u8>) {
+                output.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read_le(bytes: &[u8]) -> Self {
+                <$T>::from_le_bytes(*arrayref::array_ref![bytes, 0, size_of::<$T>()])
+            }
+        }
+    };
+}
+
+impl_wire_type!(u8);
+impl_wire_type!(u16);
+impl_wire_type!(u32);
+impl_wire_type!(u64);
+
+pub(crate) fn to_bytes<T: Delta + FoR + WrappingAdd + WireType>(
+    array: &BitPackedArray<T>,
+) -> Vec<u8> {
+    let (encoding, metadata): (u8, &[T]) = match array.encoding() {
+        Encoding::BitPacked => (0, &[]),
+        Encoding::FoR { references } => (1, references),
+        Encoding::Delta { bases } => (2, bases),
+    };
+
+    let mut output =
+        Vec::with_capacity(HEADER_LEN + (metadata.len() + array.packed().len()) * size_of::<T>());
+    output.extend_from_slice(&MAGIC);
+    output.push(VERSION);
+    output.push(T::T as u8);
+    output.push(encoding);
+    output.push(array.width() as u8);
+    output.extend_from_slice(&(array.len() as u64).to_le_bytes());
+    for &value in metadata.iter().chain(array.packed()) {
+        value.write_le(&mut output);
+    }
+    output
+}
+
+pub(crate) fn from_bytes<T: Delta + FoR + WrappingAdd + WireType>(
+    bytes: &[u8],
+) -> Result<BitPackedArray<T>, WireError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(WireError::LengthMismatch {
+            expected: HEADER_LEN,
+            actual: bytes.len(),
+        });
+    }
+    let header = arrayref::array_ref![bytes, 0, HEADER_LEN];
+    let (magic, version, element, encoding, width, len) =
+        arrayref::array_refs![header, 4, 1, 1, 1, 1, 8];
+
+    if *magic != MAGIC {
+        return Err(WireError::InvalidMagic(*magic));
+    }
+    if version[0] != VERSION {
+        return Err(WireError::UnsupportedVersion(version[0]));
+    }
+    if element[0] as usize != T::T {
+        return Err(WireError::ElementTypeMismatch {
+            expected: T::T as u8,
+            actual: element[0],
+        });
+    }
+    if width[0] as usize > T::T {
+        return Err(WireError::InvalidWidth(width[0]));
+    }
+    let len = u64::from_le_bytes(*len);
+    let len = usize::try_from(len).map_err(|_| WireError::LengthOverflow(len))?;
+
+    let width = width[0] as usize;
+    let chunks = len.div_ceil(1024);
+    // The header is untrusted, so every size derived from it is checked for overflow.
+    let overflow = || WireError::LengthOverflow(len as u64);
+    let metadata_len = match encoding[0] {
+        0 => Some(0),
+        1 => Some(chunks),
+        2 => chunks.checked_mul(T::LANES),
+        encoding => return Err(WireError::InvalidEncoding(encoding)),
+    }
+    .ok_or_else(overflow)?;
+    let packed_len = chunks
+        .checked_mul(128 * width / size_of::<T>())
+        .ok_or_else(overflow)?;
+
+    let expected = metadata_len
+        .checked_add(packed_len)
+        .and_then(|values| values.checked_mul(size_of::<T>()))
+        .and_then(|body| body.checked_add(HEADER_LEN))
+        .ok_or_else(overflow)?;
+    if bytes.len() != expected {
+        return Err(WireError::LengthMismatch {
+            expected,
+            actual: bytes.len(),
+        });
+    }
+
+    let mut values = bytes[HEADER_LEN..]
+        .chunks_exact(size_of::<T>())
+        .map(T::read_le);
+    let metadata: Vec<T> = values.by_ref().take(metadata_len).collect();
+    let packed: Vec<T> = values.collect();
+
+    let encoding = match encoding[0] {
+        0 => Encoding::BitPacked,
+        1 => Encoding::FoR {
+            references: metadata,
+        },
+        _ => Encoding::Delta { bases: metadata },
+    };
+    Ok(BitPackedArray::from_parts(width, len, packed, encoding))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let values: Vec<u32> = (0..2500).map(|i| 1000 + i * 3).collect();
+        for array in [
+            BitPackedArray::pack(12, &values),
+            BitPackedArray::for_pack(13, &values),
+            BitPackedArray::delta_pack(2, &values),
+            BitPackedArray::pack(0, &values[..0]),
+        ] {
+            let bytes = array.to_bytes();
+            assert_eq!(bytes[..4], MAGIC);
+            assert_eq!(BitPackedArray::<u32>::from_bytes(&bytes), Ok(array));
+        }
+    }
+
+    #[test]
+    fn test_header_layout() {
+        let array = BitPackedArray::<u16>::for_pack(3, &[7, 8, 9]);
+        let bytes = array.to_bytes();
+        assert_eq!(
+            bytes[..HEADER_LEN],
+            [b'F', b'L', b'N', b'S', 1, 16, 1, 3, 3, 0, 0, 0, 0, 0, 0, 0]
+        );
+        // One reference, then one chunk of 1024 * 3 bits.
+        assert_eq!(bytes[HEADER_LEN..HEADER_LEN + 2], [7, 0]);
+        assert_eq!(bytes.len(), HEADER_LEN + 2 + 384);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let bytes = BitPackedArray::<u16>::pack(3, &[1, 2, 3]).to_bytes();
+        let from_bytes = BitPackedArray::<u16>::from_bytes;
+
+        assert_eq!(
+            from_bytes(&bytes[..10]),
+            Err(WireError::LengthMismatch {
+                expected: HEADER_LEN,
+                actual: 10
+            })
+        );
+        assert_eq!(
+            from_bytes(&bytes[..bytes.len() - 1]),
+            Err(WireError::LengthMismatch {
+                expected: bytes.len(),
+                actual: bytes.len() - 1
+            })
+        );
+        assert_eq!(
+            BitPackedArray::<u32>::from_bytes(&bytes),
+            Err(WireError::ElementTypeMismatch {
+                expected: 32,
+                actual: 16
+            })
+        );
+
+        let mut corrupt = bytes.clone();
+        corrupt[0] = b'X';
+        assert_eq!(from_bytes(&corrupt), Err(WireError::InvalidMagic(*b"XLNS")));
+
+        let mut corrupt = bytes.clone();
+        corrupt[4] = 2;
+        assert_eq!(from_bytes(&corrupt), Err(WireError::UnsupportedVersion(2)));
+
+        let mut corrupt = bytes.clone();
+        corrupt[6] = 3;
+        assert_eq!(from_bytes(&corrupt), Err(WireError::InvalidEncoding(3)));
+
+        let mut corrupt = bytes;
+        corrupt[7] = 17;
+        assert_eq!(from_bytes(&corrupt), Err(WireError::InvalidWidth(17)));
+
+        // A huge length must not overflow the expected buffer size.
+        let mut corrupt = BitPackedArray::<u64>::pack(64, &[1]).to_bytes();
+        corrupt[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(
+            BitPackedArray::<u64>::from_bytes(&corrupt),
+            Err(WireError::LengthOverflow(u64::MAX))
+        );
+        corrupt[6] = 2;
+        assert_eq!(
+            BitPackedArray::<u64>::from_bytes(&corrupt),
+            Err(WireError::LengthOverflow(u64::MAX))
+        );
+    }
+}
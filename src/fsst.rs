@@ -0,0 +1,353 @@
+//! FSST (Fast Static Symbol Table) string compression.
+//!
+//! A [`SymbolTable`] of up to 255 symbols of 1 to 8 bytes each is trained on a sample of the
+//! strings. Compression greedily replaces the longest matching symbol with its one byte code, and
+//! bytes not covered by any symbol are written as [`ESCAPE`] followed by the byte itself. A
+//! [`StringArray`] stores the codes of a whole string column, together with the offsets of every
+//! string as a `Delta` packed `u32` vector.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::{Delta, SliceHeader};
+
+/// The code that precedes a literal byte.
+pub const ESCAPE: u8 = 255;
+
+/// The maximum number of symbols in a [`SymbolTable`], i.e. all codes except [`ESCAPE`].
+pub const MAX_SYMBOLS: usize = 255;
+
+const MAX_SYMBOL_LEN: usize = 8;
+const GENERATIONS: usize = 5;
+const SAMPLE_BYTES: usize = 1 << 14;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Symbol {
+    bytes: [u8; MAX_SYMBOL_LEN],
+    len: u8,
+}
+
+impl Symbol {
+    fn new(bytes: &[u8]) -> Self {
+        let len = bytes.len().min(MAX_SYMBOL_LEN);
+        let mut symbol = Self {
+            bytes: [0; MAX_SYMBOL_LEN],
+            len: len as u8,
+        };
+        symbol.bytes[..len].copy_from_slice(&bytes[..len]);
+        symbol
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+/// A static table of up to [`MAX_SYMBOLS`] symbols of 1 to 8 bytes each.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolTable {
+    symbols: Vec<Symbol>,
+    /// The codes of the symbols starting with each byte, longest symbol first.
+    by_first_byte: Vec<Vec<u8>>,
+}
+
+impl SymbolTable {
+    fn from_symbols(symbols: Vec<Symbol>) -> Self {
+        let mut by_first_byte = alloc::vec![Vec::new(); 256];
+        for (code, symbol) in symbols.iter().enumerate() {
+            by_first_byte[symbol.bytes[0] as usize].push(code as u8);
+        }
+        for codes in &mut by_first_byte {
+            codes.sort_by_key(|&code| core::cmp::Reverse(symbols[code as usize].len));
+        }
+        Self {
+            symbols,
+            by_first_byte,
+        }
+    }
+
+    /// Trains a symbol table on `sample`.
+    ///
+    /// Every generation compresses the sample with the current table, and then keeps the
+    /// symbols, and concatenations of adjacent symbols, that cover the most bytes.
+    pub fn train<S: AsRef<[u8]>>(sample: &[S]) -> Self {
+        let mut table = Self::from_symbols(Vec::new());
+        for _ in 0..GENERATIONS {
+            // Codes above `MAX_SYMBOLS` are literal bytes.
+            let mut counts = BTreeMap::<u16, usize>::new();
+            let mut pair_counts = BTreeMap::<(u16, u16), usize>::new();
+            for string in sample {
+                let mut input = string.as_ref();
+                let mut prev = None;
+                while let Some(&byte) = input.first() {
+                    let code = match table.find(input) {
+                        Some(code) => u16::from(code),
+                        None => 256 + u16::from(byte),
+                    };
+                    *counts.entry(code).or_default() += 1;
+                    if let Some(prev) = prev {
+                        *pair_counts.entry((prev, code)).or_default() += 1;
+                    }
+                    input = &input[table.symbol_len(code)..];
+                    prev = Some(code);
+                }
+            }
+
+            // The gain of a candidate symbol is the number of bytes it would cover.
+            let mut gains = BTreeMap::<Symbol, usize>::new();
+            for (&code, &count) in &counts {
+                let symbol = table.symbol(code);
+                *gains.entry(symbol).or_default() += count * symbol.len as usize;
+            }
+            for (&(first, second), &count) in &pair_counts {
+                let mut bytes = Vec::with_capacity(2 * MAX_SYMBOL_LEN);
+                bytes.extend_from_slice(table.symbol(first).as_bytes());
+                bytes.extend_from_slice(table.symbol(second).as_bytes());
+                let symbol = Symbol::new(&bytes);
+                *gains.entry(symbol).or_default() += count * symbol.len as usize;
+            }
+
+            let mut candidates: Vec<_> = gains.into_iter().collect();
+            candidates.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.len.cmp(&a.0.len)));
+            candidates.truncate(MAX_SYMBOLS);
+            table = Self::from_symbols(candidates.into_iter().map(|(symbol, _)| symbol).collect());
+        }
+        table
+    }
+
+    /// The number of symbols in the table.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Returns the bytes of the symbol with the given `code`, if any.
+    #[must_use]
+    pub fn get(&self, code: u8) -> Option<&[u8]> {
+        self.symbols.get(code as usize).map(Symbol::as_bytes)
+    }
+
+    /// Returns the code of the longest symbol that `input` starts with.
+    fn find(&self, input: &[u8]) -> Option<u8> {
+        self.by_first_byte[input[0] as usize]
+            .iter()
+            .copied()
+            .find(|&code| input.starts_with(self.symbols[code as usize].as_bytes()))
+    }
+
+    fn symbol(&self, code: u16) -> Symbol {
+        match self.symbols.get(code as usize) {
+            Some(&symbol) => symbol,
+            None => Symbol::new(&[(code - 256) as u8]),
+        }
+    }
+
+    fn symbol_len(&self, code: u16) -> usize {
+        self.symbols
+            .get(code as usize)
+            .map_or(1, |s| s.len as usize)
+    }
+
+    /// Compresses `input`, appending the codes to `output`.
+    pub fn compress(&self, input: &[u8], output: &mut Vec<u8>) {
+        let mut input = input;
+        while let Some(&byte) = input.first() {
+            if let Some(code) = self.find(input) {
+                output.push(code);
+                input = &input[self.symbols[code as usize].len as usize..];
+            } else {
+                output.extend_from_slice(&[ESCAPE, byte]);
+                input = &input[1..];
+            }
+        }
+    }
+
+    /// Decompresses codes written by [`SymbolTable::compress`], appending the bytes to `output`.
+    ///
+    /// # Panics
+    /// Panics if `input` contains a code that is not in the table, or ends in [`ESCAPE`].
+    pub fn decompress(&self, input: &[u8], output: &mut Vec<u8>) {
+        let mut codes = input.iter();
+        while let Some(&code) = codes.next() {
+            if code == ESCAPE {
+                let literal = codes.next();
+                assert!(literal.is_some(), "Escape code must be followed by a byte");
+                output.extend(literal);
+            } else {
+                output.extend_from_slice(self.symbols[code as usize].as_bytes());
+            }
+        }
+    }
+}
+
+/// An FSST compressed string column.
+///
+/// The codes of all strings are concatenated, and the `len + 1` offsets of the strings into the
+/// codes are `Delta` packed. Offsets only grow by the compressed length of a string, so they pack
+/// into the bit-width of the longest compressed string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StringArray {
+    table: SymbolTable,
+    codes: Vec<u8>,
+    len: usize,
+    width: usize,
+    bases: Vec<u32>,
+    offsets: Vec<u32>,
+}
+
+impl StringArray {
+    /// Compresses `strings` with a symbol table trained on a sample of them.
+    ///
+    /// # Panics
+    /// Panics if the compressed strings exceed `u32::MAX` bytes.
+    pub fn compress<S: AsRef<[u8]>>(strings: &[S]) -> Self {
+        let bytes: usize = strings.iter().map(|s| s.as_ref().len()).sum();
+        let stride = bytes.div_ceil(SAMPLE_BYTES).max(1);
+        let sample: Vec<&[u8]> = strings.iter().step_by(stride).map(AsRef::as_ref).collect();
+        Self::compress_with(SymbolTable::train(&sample), strings)
+    }
+
+    /// Compresses `strings` with an existing symbol table.
+    ///
+    /// # Panics
+    /// Panics if the compressed strings exceed `u32::MAX` bytes.
+    pub fn compress_with<S: AsRef<[u8]>>(table: SymbolTable, strings: &[S]) -> Self {
+        let mut codes = Vec::new();
+        let mut offsets = Vec::with_capacity(strings.len() + 1);
+        let mut max_len = 0;
+        offsets.push(0);
+        for string in strings {
+            let start = codes.len();
+            table.compress(string.as_ref(), &mut codes);
+            max_len = max_len.max(codes.len() - start);
+            let offset = u32::try_from(codes.len());
+            assert!(offset.is_ok(), "Compressed strings exceed u32::MAX bytes");
+            offsets.extend(offset);
+        }
+
+        let width = (usize::BITS - max_len.leading_zeros()) as usize;
+        let mut bases = Vec::new();
+        let mut packed = Vec::new();
+        u32::delta_pack_slice(width, &offsets, &mut bases, &mut packed);
+        Self {
+            table,
+            codes,
+            len: strings.len(),
+            width,
+            bases,
+            offsets: packed,
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub fn table(&self) -> &SymbolTable {
+        &self.table
+    }
+
+    /// The concatenated codes of all strings.
+    #[must_use]
+    pub fn codes(&self) -> &[u8] {
+        &self.codes
+    }
+
+    /// Decodes the `len + 1` offsets of the strings into [`StringArray::codes`], appending them
+    /// to `output`.
+    pub fn decode_offsets(&self, output: &mut Vec<u32>) {
+        let header = SliceHeader {
+            width: self.width,
+            len: self.len + 1,
+        };
+        u32::undelta_pack_slice(header, &self.offsets, &self.bases, output);
+    }
+
+    /// Decompresses all strings, appending them to `output`.
+    pub fn decode_into(&self, output: &mut Vec<Vec<u8>>) {
+        let mut offsets = Vec::with_capacity(self.len + 1);
+        self.decode_offsets(&mut offsets);
+        output.reserve(self.len);
+        for offset in offsets.windows(2) {
+            let mut string = Vec::new();
+            self.table.decompress(
+                &self.codes[offset[0] as usize..offset[1] as usize],
+                &mut string,
+            );
+            output.push(string);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::format;
+    use alloc::string::String;
+
+    #[test]
+    fn test_symbol_table() {
+        let sample = ["hello world", "hello there", "world peace"].repeat(10);
+        let table = SymbolTable::train(&sample);
+        assert!(!table.is_empty() && table.len() <= MAX_SYMBOLS);
+
+        for input in ["hello world", "unseen \u{1F600} bytes", ""] {
+            let mut compressed = Vec::new();
+            table.compress(input.as_bytes(), &mut compressed);
+            let mut decompressed = Vec::new();
+            table.decompress(&compressed, &mut decompressed);
+            assert_eq!(decompressed, input.as_bytes());
+        }
+
+        let mut compressed = Vec::new();
+        table.compress(b"hello world", &mut compressed);
+        assert!(compressed.len() < 4, "{compressed:?}");
+    }
+
+    #[test]
+    fn test_empty_table() {
+        let table = SymbolTable::train::<&[u8]>(&[]);
+        let mut compressed = Vec::new();
+        table.compress(b"abc", &mut compressed);
+        assert_eq!(compressed, [ESCAPE, b'a', ESCAPE, b'b', ESCAPE, b'c']);
+    }
+
+    #[test]
+    fn test_string_array() {
+        let strings: Vec<String> = (0..3000)
+            .map(|i| format!("https://example.com/users/{}/profile", i % 97))
+            .chain([String::new()])
+            .collect();
+        let array = StringArray::compress(&strings);
+        assert_eq!(array.len(), strings.len());
+        assert!(array.codes().len() * 3 < strings.iter().map(String::len).sum());
+
+        let mut offsets = Vec::new();
+        array.decode_offsets(&mut offsets);
+        assert_eq!(offsets.len(), strings.len() + 1);
+        assert_eq!(
+            offsets.last().map(|&o| o as usize),
+            Some(array.codes().len())
+        );
+
+        let mut decoded = Vec::new();
+        array.decode_into(&mut decoded);
+        assert!(decoded.iter().eq(strings.iter().map(String::as_bytes)));
+
+        let mut decoded = Vec::new();
+        StringArray::compress::<&str>(&[]).decode_into(&mut decoded);
+        assert!(decoded.is_empty());
+    }
+}
@@ -2,22 +2,60 @@
 #![feature(generic_const_exprs)]
 #![no_std]
 
+#[cfg(feature = "alloc")]
 extern crate alloc;
 extern crate core;
 
 use core::mem::size_of;
 use num_traits::{PrimInt, Unsigned};
 
+mod aggregate;
+mod alp;
+mod alp_rd;
 mod bitpacking;
+#[cfg(feature = "alloc")]
+mod cascade;
+mod compare;
 mod delta;
+mod dict;
 mod ffor;
+#[cfg(feature = "alloc")]
+mod format;
+#[cfg(feature = "alloc")]
+mod fsst;
+mod linear;
 mod macros;
+mod mask;
+mod nullable;
+#[cfg(feature = "alloc")]
+mod packed;
+mod rle;
 mod transpose;
+mod util;
+mod zigzag;
 
+pub use aggregate::*;
+pub use alp::*;
+pub use alp_rd::*;
 pub use bitpacking::*;
+#[cfg(feature = "alloc")]
+pub use cascade::*;
+pub use compare::*;
 pub use delta::*;
+pub use dict::*;
 pub use ffor::*;
+#[cfg(feature = "alloc")]
+pub use format::*;
+#[cfg(feature = "alloc")]
+pub use fsst::*;
+pub use linear::*;
+pub use mask::*;
+pub use nullable::*;
+#[cfg(feature = "alloc")]
+pub use packed::*;
+pub use rle::*;
 pub use transpose::*;
+pub use zigzag::*;
 
 pub const FL_ORDER: [usize; 8] = [0, 4, 2, 6, 1, 5, 3, 7];
 
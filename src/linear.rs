@@ -0,0 +1,257 @@
+use arrayref::{array_mut_ref, array_ref};
+use core::mem::size_of;
+use paste::paste;
+
+use crate::{pack, seq_t, unpack, BitPackWidth, FastLanes, SupportedBitPackWidth};
+
+/// `BitPack` with the rows of every lane in plain row-major order.
+///
+/// Unlike [`crate::BitPacking`], which iterates the rows of a lane in the transposed `FL_ORDER`
+/// to enable fused transposed encodings, this iterates rows in plain row-major order: element `i`
+/// is row `i / LANES` of lane `i % LANES`. This is meant to match the addressing of the scalar
+/// kernels of the original C++ `FastLanes` implementation, but it has only been checked against
+/// an independent transcription of that addressing, not against the kernels themselves. It is
+/// not binary compatible with them until it is, see `tests/golden/generate.c`.
+pub trait RowMajorBitPacking: FastLanes {
+    /// Packs 1024 elements into W bits each.
+    fn pack<const W: usize>(input: &[Self; 1024], output: &mut [Self; 1024 * W / Self::T])
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Packs 1024 elements into `W` bits each, where `W` is runtime-known instead of
+    /// compile-time known.
+    ///
+    /// # Safety
+    /// The input slice must be of exactly length 1024. The output slice must be of length
+    /// `1024 * W / T`, where `T` is the bit-width of Self and `W` is the packed width.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_pack(width: usize, input: &[Self], output: &mut [Self]);
+
+    /// Unpacks 1024 elements from `W` bits each.
+    fn unpack<const W: usize>(input: &[Self; 1024 * W / Self::T], output: &mut [Self; 1024])
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Unpacks 1024 elements from `W` bits each, where `W` is runtime-known instead of
+    /// compile-time known.
+    ///
+    /// # Safety
+    /// The input slice must be of length `1024 * W / T`, where `T` is the bit-width of Self and `W`
+    /// is the packed width. The output slice must be of exactly length 1024.
+    /// These lengths are checked only with `debug_assert` (i.e., not checked on release builds).
+    unsafe fn unchecked_unpack(width: usize, input: &[Self], output: &mut [Self]);
+
+    /// Unpacks a single element at the provided index from a packed array of 1024 `W` bit elements.
+    fn unpack_single<const W: usize>(packed: &[Self; 1024 * W / Self::T], index: usize) -> Self
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+}
+
+macro_rules! impl_row_major_packing {
+    ($T:ty) => {
+        paste! {
+            impl RowMajorBitPacking for $T {
+                fn pack<const W: usize>(
+                    input: &[Self; 1024],
+                    output: &mut [Self; 1024 * W / Self::T],
+                ) where BitPackWidth<W>: SupportedBitPackWidth<Self> {
+                    for lane in 0..Self::LANES {
+                        pack!(@linear $T, W, output, lane, |$idx| {
+                            input[$idx]
+                        });
+                    }
+                }
+
+                unsafe fn unchecked_pack(width: usize, input: &[Self], output: &mut [Self]) {
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(output.len(), packed_len, "Output buffer must be of size 1024 * W / T");
+                    debug_assert_eq!(input.len(), 1024, "Input buffer must be of size 1024");
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => <Self as RowMajorBitPacking>::pack::<W>(
+                                array_ref![input, 0, 1024],
+                                array_mut_ref![output, 0, 1024 * W / <$T>::T],
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => <Self as RowMajorBitPacking>::pack::<{ Self::T }>(
+                                array_ref![input, 0, 1024],
+                                array_mut_ref![output, 0, 1024],
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
+                fn unpack<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    output: &mut [Self; 1024],
+                ) where BitPackWidth<W>: SupportedBitPackWidth<Self> {
+                    for lane in 0..Self::LANES {
+                        unpack!(@linear $T, W, input, lane, |$idx, $elem| {
+                            output[$idx] = $elem
+                        });
+                    }
+                }
+
+                unsafe fn unchecked_unpack(width: usize, input: &[Self], output: &mut [Self]) {
+                    let packed_len = 128 * width / size_of::<Self>();
+                    debug_assert_eq!(input.len(), packed_len, "Input buffer must be of size 1024 * W / T");
+                    debug_assert_eq!(output.len(), 1024, "Output buffer must be of size 1024");
+                    debug_assert!(width <= Self::T, "Width must be less than or equal to {}", Self::T);
+
+                    seq_t!(W in $T {
+                        match width {
+                            #(W => <Self as RowMajorBitPacking>::unpack::<W>(
+                                array_ref![input, 0, 1024 * W / <$T>::T],
+                                array_mut_ref![output, 0, 1024],
+                            ),)*
+                            // seq_t has exclusive upper bound
+                            Self::T => <Self as RowMajorBitPacking>::unpack::<{ Self::T }>(
+                                array_ref![input, 0, 1024],
+                                array_mut_ref![output, 0, 1024],
+                            ),
+                            _ => unreachable!("Unsupported width: {}", width)
+                        }
+                    })
+                }
+
+                fn unpack_single<const W: usize>(packed: &[Self; 1024 * W / Self::T], index: usize) -> Self
+                where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    if W == 0 {
+                        // Special case for W=0, we just need to zero the output.
+                        return 0 as $T;
+                    }
+
+                    assert!(index < 1024, "Index must be less than 1024, got {}", index);
+                    let (lane, row) = (index % Self::LANES, index / Self::LANES);
+
+                    if W == <$T>::T {
+                        // Special case for W==T, we can just read the value directly
+                        return packed[<$T>::LANES * row + lane];
+                    }
+
+                    let mask: $T = (1 << (W % <$T>::T)) - 1;
+                    let start_bit = row * W;
+                    let start_word = start_bit / <$T>::T;
+                    let lo_shift = start_bit % <$T>::T;
+                    let remaining_bits = <$T>::T - lo_shift;
+
+                    let lo = packed[<$T>::LANES * start_word + lane] >> lo_shift;
+                    return if remaining_bits >= W {
+                        // in this case we will mask out all bits of hi word
+                        lo & mask
+                    } else {
+                        // guaranteed that lo_shift > 0 and thus remaining_bits < T
+                        let hi = packed[<$T>::LANES * (start_word + 1) + lane] << remaining_bits;
+                        (lo | hi) & mask
+                    };
+                }
+            }
+        }
+    };
+}
+
+impl_row_major_packing!(u8);
+impl_row_major_packing!(u16);
+impl_row_major_packing!(u32);
+impl_row_major_packing!(u64);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::BitPacking;
+    use core::array;
+    use core::fmt::Debug;
+
+    /// The values the golden vectors in `tests/golden` were generated from, see `generate.c`. The
+    /// vectors come from a transcription of the upstream addressing, not the upstream kernels.
+    fn golden_values<T: FastLanes>(width: usize) -> [T; 1024] {
+        array::from_fn(|i| {
+            let x = (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+            T::from((x >> 32) & ((1 << width) - 1)).unwrap()
+        })
+    }
+
+    fn try_golden<T: RowMajorBitPacking + BitPacking + Debug, const W: usize>(golden: &[u8])
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<T>,
+        [(); 1024 * W / T::T]:,
+    {
+        let values = golden_values::<T>(W);
+
+        let mut packed = [T::zero(); 1024 * W / T::T];
+        RowMajorBitPacking::pack::<W>(&values, &mut packed);
+
+        let bytes_per_word = size_of::<T>();
+        assert_eq!(golden.len(), packed.len() * bytes_per_word);
+        for (i, word) in golden.chunks_exact(bytes_per_word).enumerate() {
+            let expected = word.iter().enumerate().fold(T::zero(), |acc, (b, &byte)| {
+                acc | T::from(byte).unwrap() << (8 * b)
+            });
+            assert_eq!(packed[i], expected, "Mismatch at word {i}");
+        }
+
+        let mut unpacked = [T::zero(); 1024];
+        RowMajorBitPacking::unpack::<W>(&packed, &mut unpacked);
+        assert_eq!(values, unpacked);
+
+        let mut unchecked = [T::zero(); 1024];
+        unsafe { RowMajorBitPacking::unchecked_unpack(W, &packed, &mut unchecked) };
+        assert_eq!(values, unchecked);
+
+        for i in 0..1024 {
+            assert_eq!(
+                RowMajorBitPacking::unpack_single::<W>(&packed, i),
+                values[i]
+            );
+        }
+
+        // The transposed layout differs in the order of the rows within each lane, except for u8
+        // where each lane has a single octet of rows and the two orders coincide.
+        let mut transposed = [T::zero(); 1024 * W / T::T];
+        BitPacking::pack::<W>(&values, &mut transposed);
+        assert_eq!(packed == transposed, T::T == 8);
+    }
+
+    #[test]
+    fn test_golden_u8_w3() {
+        try_golden::<u8, 3>(include_bytes!("../tests/golden/u8_w3.bin"));
+    }
+
+    #[test]
+    fn test_golden_u16_w7() {
+        try_golden::<u16, 7>(include_bytes!("../tests/golden/u16_w7.bin"));
+    }
+
+    #[test]
+    fn test_golden_u32_w3() {
+        try_golden::<u32, 3>(include_bytes!("../tests/golden/u32_w3.bin"));
+    }
+
+    #[test]
+    fn test_golden_u32_w17() {
+        try_golden::<u32, 17>(include_bytes!("../tests/golden/u32_w17.bin"));
+    }
+
+    #[test]
+    fn test_golden_u64_w13() {
+        try_golden::<u64, 13>(include_bytes!("../tests/golden/u64_w13.bin"));
+    }
+
+    #[test]
+    fn test_unchecked_pack() {
+        let input = array::from_fn(|i| i as u32);
+        let mut packed = [0; 320];
+        unsafe { RowMajorBitPacking::unchecked_pack(10, &input, &mut packed) };
+        let mut output = [0; 1024];
+        unsafe { RowMajorBitPacking::unchecked_unpack(10, &packed, &mut output) };
+        assert_eq!(input, output);
+
+        // Row-major: the first word of lane 0 holds elements 0, 32 and 64 (and part of 96).
+        assert_eq!(packed[0], (96 << 30) | (64 << 20) | (32 << 10));
+    }
+}
@@ -7,20 +7,22 @@
 /// be used to easily generated fused kernels with transposed encodings such as delta.
 ///
 /// Essentially this means: BitPack(Delta(Transpose(V))) == Delta+BitPack(Transpose(V))
+///
+/// `pack!` and `unpack!` also accept a leading `@linear` ordering, e.g. `pack!(@linear u32, ...)`,
+/// which iterates the rows of every lane in plain row-major order. This is what
+/// `RowMajorBitPacking` uses.
 
 #[macro_export]
 macro_rules! iterate {
     ($T:ty, $lane: expr, | $_1:tt $idx:ident | $($body:tt)*) => {
         macro_rules! __kernel__ {( $_1 $idx:ident ) => ( $($body)* )}
         {
-            use $crate::{seq_t, FL_ORDER};
+            use $crate::seq_t;
             use paste::paste;
 
             #[inline(always)]
             fn index(row: usize, lane: usize) -> usize {
-                let o = row / 8;
-                let s = row % 8;
-                (FL_ORDER[o] * 16) + (s * 128) + lane
+                $crate::fl_index(row, lane)
             }
 
             paste!(seq_t!(row in $T {
@@ -31,12 +33,27 @@ macro_rules! iterate {
     }
 }
 
+/// The index of the element at `row` of `lane` for the given iteration order.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __index__ {
+    (@transposed $T:ty, $row:expr, $lane:expr) => {
+        $crate::fl_index($row, $lane)
+    };
+    (@linear $T:ty, $row:expr, $lane:expr) => {
+        $row * <$T>::LANES + $lane
+    };
+}
+
 #[macro_export]
 macro_rules! pack {
     ($T:ty, $W:expr, $packed:expr, $lane:expr, | $_1:tt $idx:ident | $($body:tt)*) => {
+        $crate::pack!(@transposed $T, $W, $packed, $lane, | $_1 $idx | $($body)*)
+    };
+    (@$order:ident $T:ty, $W:expr, $packed:expr, $lane:expr, | $_1:tt $idx:ident | $($body:tt)*) => {
         macro_rules! __kernel__ {( $_1 $idx:ident ) => ( $($body)* )}
         {
-            use $crate::{seq_t, FL_ORDER};
+            use $crate::seq_t;
             use paste::paste;
 
             // The number of bits of T.
@@ -44,9 +61,7 @@ macro_rules! pack {
 
             #[inline(always)]
             fn index(row: usize, lane: usize) -> usize {
-                let o = row / 8;
-                let s = row % 8;
-                (FL_ORDER[o] * 16) + (s * 128) + lane
+                $crate::__index__!(@$order $T, row, lane)
             }
 
             if $W == 0 {
@@ -100,9 +115,12 @@ macro_rules! pack {
 #[macro_export]
 macro_rules! unpack {
     ($T:ty, $W:expr, $packed:expr, $lane:expr, | $_1:tt $idx:ident, $_2:tt $elem:ident | $($body:tt)*) => {
+        $crate::unpack!(@transposed $T, $W, $packed, $lane, | $_1 $idx, $_2 $elem | $($body)*)
+    };
+    (@$order:ident $T:ty, $W:expr, $packed:expr, $lane:expr, | $_1:tt $idx:ident, $_2:tt $elem:ident | $($body:tt)*) => {
         macro_rules! __kernel__ {( $_1 $idx:ident, $_2 $elem:ident ) => ( $($body)* )}
         {
-            use $crate::{seq_t, FL_ORDER};
+            use $crate::seq_t;
             use paste::paste;
 
             // The number of bits of T.
@@ -110,9 +128,7 @@ macro_rules! unpack {
 
             #[inline(always)]
             fn index(row: usize, lane: usize) -> usize {
-                let o = row / 8;
-                let s = row % 8;
-                (FL_ORDER[o] * 16) + (s * 128) + lane
+                $crate::__index__!(@$order $T, row, lane)
             }
 
             if $W == 0 {
@@ -0,0 +1,249 @@
+use crate::{fl_index, rows_by_index, FL_ORDER};
+use core::ops::{BitAnd, BitOr, BitXor, Not};
+
+/// The `u64` row of every index, i.e. the bit that holds it within its mask word.
+pub(crate) const MASK_ROWS: [u8; 1024] = rows_by_index::<u64>();
+
+/// Returns whether the element at `index` is selected by the words of a [`Mask1024`].
+#[must_use]
+pub fn is_selected(mask: &[u64; 16], index: usize) -> bool {
+    (mask[index % 16] >> MASK_ROWS[index]) & 1 == 1
+}
+
+/// Writes the indices of the selected elements to `indices` in ascending order, and returns how
+/// many there are. `indices` must have room for all of them.
+pub(crate) fn selected_indices(mask: &Mask1024, indices: &mut [u16]) -> usize {
+    let mut n = 0;
+    for (lane, &word) in mask.0.iter().enumerate() {
+        let mut word = word;
+        while word != 0 {
+            let row = word.trailing_zeros() as usize;
+            indices[n] = fl_index(row, lane) as u16;
+            n += 1;
+            word &= word - 1;
+        }
+    }
+    indices[..n].sort_unstable();
+    n
+}
+
+/// The positions the selected elements of a mask compact to in ascending index order, for
+/// kernels that visit the elements in lane order.
+pub(crate) struct Positions {
+    /// The bits of the 16 consecutive indices of every group, see `Mask1024::group`.
+    groups: [u16; 64],
+    /// The number of selected elements before every group.
+    offsets: [u16; 64],
+}
+
+impl Positions {
+    pub(crate) fn new(mask: &Mask1024) -> Self {
+        let mut groups = [0; 64];
+        let mut offsets = [0; 64];
+        let mut n = 0;
+        for group in 0..64 {
+            groups[group] = mask.group(group);
+            offsets[group] = n;
+            n += groups[group].count_ones() as u16;
+        }
+        Self { groups, offsets }
+    }
+
+    /// Returns the position of the element at `index`, or `None` if it is not selected.
+    #[inline]
+    pub(crate) fn get(&self, index: usize) -> Option<usize> {
+        let (bits, bit) = (self.groups[index / 16], index % 16);
+        if (bits >> bit) & 1 == 0 {
+            return None;
+        }
+        let below = (bits & ((1 << bit) - 1)).count_ones() as usize;
+        Some(self.offsets[index / 16] as usize + below)
+    }
+}
+
+/// A 1024-bit mask in the layout written by [`Compare`](crate::Compare), i.e. a vector of 1024
+/// booleans packed into 1 bit each as `u64`s. Indices are those of the vector, so the set bits of
+/// a selection line up with the elements they select.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Mask1024(pub [u64; 16]);
+
+impl Mask1024 {
+    pub const NONE: Self = Self([0; 16]);
+    pub const ALL: Self = Self([u64::MAX; 16]);
+
+    #[must_use]
+    pub fn get(&self, index: usize) -> bool {
+        is_selected(&self.0, index)
+    }
+
+    pub fn set(&mut self, index: usize, value: bool) {
+        let bit = 1 << MASK_ROWS[index];
+        if value {
+            self.0[index % 16] |= bit;
+        } else {
+            self.0[index % 16] &= !bit;
+        }
+    }
+
+    /// The number of set bits.
+    #[must_use]
+    pub fn count_ones(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// The number of set bits at indices below `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than 1024.
+    #[must_use]
+    pub fn rank(&self, index: usize) -> usize {
+        assert!(index <= 1024, "Index must be at most 1024, got {index}");
+        let full: usize = (0..index / 16)
+            .map(|group| self.group(group).count_ones() as usize)
+            .sum();
+        if index % 16 == 0 {
+            return full;
+        }
+        full + (self.group(index / 16) & ((1 << (index % 16)) - 1)).count_ones() as usize
+    }
+
+    /// The index of the set bit of rank `rank`, i.e. with `rank` set bits below it, if any.
+    #[must_use]
+    pub fn select(&self, rank: usize) -> Option<usize> {
+        let mut rank = rank;
+        for group in 0..64 {
+            let mut bits = self.group(group);
+            let count = bits.count_ones() as usize;
+            if rank < count {
+                for _ in 0..rank {
+                    bits &= bits - 1;
+                }
+                return Some(group * 16 + bits.trailing_zeros() as usize);
+            }
+            rank -= count;
+        }
+        None
+    }
+
+    /// Iterates over the indices of the set bits in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..64).flat_map(move |group| {
+            let bits = self.group(group);
+            (0..16)
+                .filter(move |i| (bits >> i) & 1 == 1)
+                .map(move |i| group * 16 + i)
+        })
+    }
+
+    /// The bits of the 16 consecutive indices `16 * group..16 * (group + 1)`, which share a row
+    /// across all 16 words.
+    fn group(&self, group: usize) -> u16 {
+        let row = FL_ORDER[group % 8] * 8 + group / 8;
+        let mut bits = 0;
+        for (i, &word) in self.0.iter().enumerate() {
+            bits |= (((word >> row) & 1) as u16) << i;
+        }
+        bits
+    }
+}
+
+impl From<[u64; 16]> for Mask1024 {
+    fn from(words: [u64; 16]) -> Self {
+        Self(words)
+    }
+}
+
+impl From<&[bool; 1024]> for Mask1024 {
+    fn from(bools: &[bool; 1024]) -> Self {
+        let mut mask = Self::NONE;
+        for (index, &value) in bools.iter().enumerate() {
+            mask.0[index % 16] |= u64::from(value) << MASK_ROWS[index];
+        }
+        mask
+    }
+}
+
+impl From<Mask1024> for [bool; 1024] {
+    fn from(mask: Mask1024) -> Self {
+        core::array::from_fn(|index| mask.get(index))
+    }
+}
+
+impl BitAnd for Mask1024 {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(core::array::from_fn(|i| self.0[i] & rhs.0[i]))
+    }
+}
+
+impl BitOr for Mask1024 {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(core::array::from_fn(|i| self.0[i] | rhs.0[i]))
+    }
+}
+
+impl BitXor for Mask1024 {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self {
+        Self(core::array::from_fn(|i| self.0[i] ^ rhs.0[i]))
+    }
+}
+
+impl Not for Mask1024 {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(self.0.map(|word| !word))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BitPacking, Compare};
+    use core::mem::size_of;
+
+    #[test]
+    fn test_mask1024() {
+        const W: usize = 10;
+        let values: [u32; 1024] = core::array::from_fn(|i| ((i * 37) % 1000) as u32);
+        let mut packed = [0; 128 * W / size_of::<u32>()];
+        BitPacking::pack::<W>(&values, &mut packed);
+
+        let mut low = Mask1024::NONE;
+        Compare::lt::<W>(&packed, 0, 300, &mut low);
+        let even = Mask1024::from(&core::array::from_fn(|i| i % 2 == 0));
+        assert_eq!(<[bool; 1024]>::from(low), values.map(|v| v < 300));
+
+        let both = low & even;
+        assert_eq!(<[bool; 1024]>::from(!low), values.map(|v| v >= 300));
+        assert_eq!(
+            (low | even).count_ones(),
+            low.count_ones() + even.count_ones() - both.count_ones()
+        );
+        assert_eq!(low ^ low, Mask1024::NONE);
+        assert_eq!(!Mask1024::NONE, Mask1024::ALL);
+
+        let indices = (0..1024).filter(|&i| values[i] < 300 && i % 2 == 0);
+        assert!(both.iter().eq(indices.clone()));
+        for (rank, index) in indices.clone().enumerate() {
+            assert_eq!(both.select(rank), Some(index));
+            assert_eq!(both.rank(index), rank);
+        }
+        assert_eq!(both.select(indices.clone().count()), None);
+        assert_eq!(both.rank(1024), indices.count());
+
+        let mut mask = Mask1024::default();
+        mask.set(1023, true);
+        mask.set(5, true);
+        mask.set(5, false);
+        assert!(mask.iter().eq([1023]));
+        assert_eq!(Mask1024::ALL.select(700), Some(700));
+        assert_eq!(Mask1024::ALL.rank(700), 700);
+    }
+}
@@ -0,0 +1,306 @@
+use crate::{
+    fl_index, BitPackWidth, BitPacking, Delta, FastLanes, FoR, Mask1024, SupportedBitPackWidth,
+};
+
+/// Packing of nullable vectors, whose validity is a [`Mask1024`].
+///
+/// The values of null elements are arbitrary, so before packing they are replaced by values that
+/// do not widen the packing: zero for bit-packing, the reference for `FoR` and the previous
+/// element of the lane for `Delta`. Decoding yields those fill values for null elements, and
+/// returns the validity unchanged alongside them.
+pub trait Nullable: Delta + FoR {
+    fn pack_nullable<const W: usize>(
+        input: &[Self; 1024],
+        validity: &Mask1024,
+        output: &mut [Self; 1024 * W / Self::T],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    fn unpack_nullable<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        validity: &Mask1024,
+        output: &mut [Self; 1024],
+    ) -> Mask1024
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Returns the reference and the bit-width `W` of the valid elements, see `FoR::analyze`.
+    /// A vector without valid elements packs into zero bits.
+    fn for_analyze_nullable(input: &[Self; 1024], validity: &Mask1024) -> (Self, usize);
+
+    fn for_pack_nullable<const W: usize>(
+        input: &[Self; 1024],
+        validity: &Mask1024,
+        reference: Self,
+        output: &mut [Self; 1024 * W / Self::T],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    fn unfor_pack_nullable<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        reference: Self,
+        validity: &Mask1024,
+        output: &mut [Self; 1024],
+    ) -> Mask1024
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    /// Writes the bases of a transposed vector, i.e. the first valid element of each lane, and
+    /// returns the bit-width `W` of the deltas between its valid elements, see `Delta::analyze`.
+    fn delta_analyze_nullable(
+        input: &[Self; 1024],
+        validity: &Mask1024,
+        base: &mut [Self; Self::LANES],
+    ) -> usize;
+
+    fn delta_pack_nullable<const W: usize>(
+        input: &[Self; 1024],
+        validity: &Mask1024,
+        base: &[Self; Self::LANES],
+        output: &mut [Self; 1024 * W / Self::T],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    fn undelta_pack_nullable<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        base: &[Self; Self::LANES],
+        validity: &Mask1024,
+        output: &mut [Self; 1024],
+    ) -> Mask1024
+    where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+}
+
+macro_rules! impl_nullable {
+    ($T:ty) => {
+        impl Nullable for $T {
+            fn pack_nullable<const W: usize>(
+                input: &[Self; 1024],
+                validity: &Mask1024,
+                output: &mut [Self; 1024 * W / Self::T],
+            ) where
+                BitPackWidth<W>: SupportedBitPackWidth<Self>,
+            {
+                let filled = core::array::from_fn(|i| if validity.get(i) { input[i] } else { 0 });
+                Self::pack::<W>(&filled, output);
+            }
+
+            fn unpack_nullable<const W: usize>(
+                input: &[Self; 1024 * W / Self::T],
+                validity: &Mask1024,
+                output: &mut [Self; 1024],
+            ) -> Mask1024
+            where
+                BitPackWidth<W>: SupportedBitPackWidth<Self>,
+            {
+                Self::unpack::<W>(input, output);
+                *validity
+            }
+
+            fn for_analyze_nullable(input: &[Self; 1024], validity: &Mask1024) -> (Self, usize) {
+                let (mut min, mut max) = (<$T>::MAX, <$T>::MIN);
+                for i in 0..1024 {
+                    if validity.get(i) {
+                        min = min.min(input[i]);
+                        max = max.max(input[i]);
+                    }
+                }
+                if min > max {
+                    return (0, 0);
+                }
+                (min, Self::T - (max - min).leading_zeros() as usize)
+            }
+
+            fn for_pack_nullable<const W: usize>(
+                input: &[Self; 1024],
+                validity: &Mask1024,
+                reference: Self,
+                output: &mut [Self; 1024 * W / Self::T],
+            ) where
+                BitPackWidth<W>: SupportedBitPackWidth<Self>,
+            {
+                let filled =
+                    core::array::from_fn(|i| if validity.get(i) { input[i] } else { reference });
+                Self::for_pack::<W>(&filled, reference, output);
+            }
+
+            fn unfor_pack_nullable<const W: usize>(
+                input: &[Self; 1024 * W / Self::T],
+                reference: Self,
+                validity: &Mask1024,
+                output: &mut [Self; 1024],
+            ) -> Mask1024
+            where
+                BitPackWidth<W>: SupportedBitPackWidth<Self>,
+            {
+                Self::unfor_pack::<W>(input, reference, output);
+                *validity
+            }
+
+            fn delta_analyze_nullable(
+                input: &[Self; 1024],
+                validity: &Mask1024,
+                base: &mut [Self; Self::LANES],
+            ) -> usize {
+                let mut or = 0;
+                for lane in 0..Self::LANES {
+                    let mut prev = None;
+                    for row in 0..Self::T {
+                        let index = fl_index(row, lane);
+                        if !validity.get(index) {
+                            continue;
+                        }
+                        match prev {
+                            Some(prev) => or |= input[index].wrapping_sub(prev),
+                            None => base[lane] = input[index],
+                        }
+                        prev = Some(input[index]);
+                    }
+                    if prev.is_none() {
+                        base[lane] = 0;
+                    }
+                }
+                Self::T - or.leading_zeros() as usize
+            }
+
+            fn delta_pack_nullable<const W: usize>(
+                input: &[Self; 1024],
+                validity: &Mask1024,
+                base: &[Self; Self::LANES],
+                output: &mut [Self; 1024 * W / Self::T],
+            ) where
+                BitPackWidth<W>: SupportedBitPackWidth<Self>,
+            {
+                let mut filled = [0; 1024];
+                for lane in 0..Self::LANES {
+                    let mut prev = base[lane];
+                    for row in 0..Self::T {
+                        let index = fl_index(row, lane);
+                        if validity.get(index) {
+                            prev = input[index];
+                        }
+                        filled[index] = prev;
+                    }
+                }
+                Self::delta_pack::<W>(&filled, base, output);
+            }
+
+            fn undelta_pack_nullable<const W: usize>(
+                input: &[Self; 1024 * W / Self::T],
+                base: &[Self; Self::LANES],
+                validity: &Mask1024,
+                output: &mut [Self; 1024],
+            ) -> Mask1024
+            where
+                BitPackWidth<W>: SupportedBitPackWidth<Self>,
+            {
+                Self::undelta_pack::<W>(input, base, output);
+                *validity
+            }
+        }
+    };
+}
+
+impl_nullable!(u8);
+impl_nullable!(u16);
+impl_nullable!(u32);
+impl_nullable!(u64);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Transpose;
+    use core::mem::size_of;
+
+    /// Every third element is null.
+    fn validity() -> Mask1024 {
+        Mask1024::from(&core::array::from_fn(|i| i % 3 != 0))
+    }
+
+    #[test]
+    fn test_pack_nullable() {
+        const W: usize = 5;
+        let validity = validity();
+        let values: [u16; 1024] = core::array::from_fn(|i| {
+            if i % 3 == 0 {
+                u16::MAX
+            } else {
+                (i % 32) as u16
+            }
+        });
+        let mut packed = [0; 128 * W / size_of::<u16>()];
+        Nullable::pack_nullable::<W>(&values, &validity, &mut packed);
+
+        let mut unpacked = [0; 1024];
+        assert_eq!(
+            Nullable::unpack_nullable::<W>(&packed, &validity, &mut unpacked),
+            validity
+        );
+        for i in 0..1024 {
+            let expected = if i % 3 == 0 { 0 } else { values[i] };
+            assert_eq!(unpacked[i], expected, "Mismatch at index {i}");
+        }
+    }
+
+    #[test]
+    fn test_for_pack_nullable() {
+        const W: usize = 7;
+        let validity = validity();
+        let values: [u32; 1024] = core::array::from_fn(|i| {
+            if i % 3 == 0 {
+                u32::MAX
+            } else {
+                1000 + (i % 100) as u32
+            }
+        });
+        assert_eq!(FoR::analyze(&values).1, 32);
+        assert_eq!(
+            Nullable::for_analyze_nullable(&values, &validity),
+            (1000, W)
+        );
+
+        let mut packed = [0; 128 * W / size_of::<u32>()];
+        Nullable::for_pack_nullable::<W>(&values, &validity, 1000, &mut packed);
+        let mut unpacked = [0; 1024];
+        let decoded = Nullable::unfor_pack_nullable::<W>(&packed, 1000, &validity, &mut unpacked);
+        assert_eq!(decoded, validity);
+        for i in 0..1024 {
+            let expected = if i % 3 == 0 { 1000 } else { values[i] };
+            assert_eq!(unpacked[i], expected, "Mismatch at index {i}");
+        }
+
+        assert_eq!(
+            Nullable::for_analyze_nullable(&values, &Mask1024::NONE),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn test_delta_pack_nullable() {
+        const W: usize = 3;
+        let validity = validity();
+        let values: [u64; 1024] = core::array::from_fn(|i| (i * 2) as u64);
+        let mut transposed = [0; 1024];
+        Transpose::transpose(&values, &mut transposed);
+        for i in (0..1024).filter(|i| i % 3 == 0) {
+            transposed[i] = u64::MAX - i as u64;
+        }
+
+        let mut base = [0; 16];
+        assert_eq!(Delta::analyze(&transposed, &mut base), 64);
+        assert_eq!(
+            Nullable::delta_analyze_nullable(&transposed, &validity, &mut base),
+            W
+        );
+
+        let mut packed = [0; 128 * W / size_of::<u64>()];
+        Nullable::delta_pack_nullable::<W>(&transposed, &validity, &base, &mut packed);
+        let mut unpacked = [0; 1024];
+        let decoded =
+            Nullable::undelta_pack_nullable::<W>(&packed, &base, &validity, &mut unpacked);
+        assert_eq!(decoded, validity);
+        for i in (0..1024).filter(|i| i % 3 != 0) {
+            assert_eq!(unpacked[i], transposed[i], "Mismatch at index {i}");
+        }
+    }
+}
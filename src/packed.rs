@@ -0,0 +1,322 @@
+use alloc::vec::Vec;
+use core::mem::size_of;
+use num_traits::WrappingAdd;
+
+use crate::{format, untranspose, Delta, FoR, SliceHeader, WireError, WireType};
+
+/// The encoding applied to every 1024-element chunk of a [`BitPackedArray`] before bit-packing,
+/// along with its per-chunk metadata.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Encoding<T> {
+    BitPacked,
+    /// Frame-of-reference, with one reference per chunk.
+    FoR {
+        references: Vec<T>,
+    },
+    /// Delta over the transposed chunk, with `T::LANES` bases per chunk.
+    Delta {
+        bases: Vec<T>,
+    },
+}
+
+/// An owned sequence of `len` values bit-packed into `width` bits each, stored as
+/// `len.div_ceil(1024)` packed chunks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitPackedArray<T> {
+    width: usize,
+    len: usize,
+    packed: Vec<T>,
+    encoding: Encoding<T>,
+}
+
+impl<T: Delta + FoR + WrappingAdd> BitPackedArray<T> {
+    /// Bit-packs `values` into `width` bits each. Bits above `width` are discarded.
+    pub fn pack(width: usize, values: &[T]) -> Self {
+        let mut packed = Vec::new();
+        T::pack_slice(width, values, &mut packed);
+        Self {
+            width,
+            len: values.len(),
+            packed,
+            encoding: Encoding::BitPacked,
+        }
+    }
+
+    /// Frame-of-reference packs `values`, using the minimum of each chunk as its reference.
+    pub fn for_pack(width: usize, values: &[T]) -> Self {
+        let mut packed = Vec::new();
+        let mut references = Vec::with_capacity(values.len().div_ceil(1024));
+        for chunk in values.chunks(1024) {
+            let reference = chunk.iter().copied().fold(T::max_value(), T::min);
+            T::for_pack_slice(width, chunk, reference, &mut packed);
+            references.push(reference);
+        }
+        Self {
+            width,
+            len: values.len(),
+            packed,
+            encoding: Encoding::FoR { references },
+        }
+    }
+
+    /// Delta packs naturally ordered `values`, see `Delta::delta_pack_slice`.
+    pub fn delta_pack(width: usize, values: &[T]) -> Self {
+        let mut packed = Vec::new();
+        let mut bases = Vec::new();
+        T::delta_pack_slice(width, values, &mut bases, &mut packed);
+        Self {
+            width,
+            len: values.len(),
+            packed,
+            encoding: Encoding::Delta { bases },
+        }
+    }
+
+    /// Assembles an array from its parts, which must be mutually consistent.
+    pub(crate) fn from_parts(
+        width: usize,
+        len: usize,
+        packed: Vec<T>,
+        encoding: Encoding<T>,
+    ) -> Self {
+        Self {
+            width,
+            len,
+            packed,
+            encoding,
+        }
+    }
+
+    /// Serializes the array into the self-describing byte format described in [`format`].
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8>
+    where
+        T: WireType,
+    {
+        format::to_bytes(self)
+    }
+
+    /// Deserializes an array written by [`BitPackedArray::to_bytes`], validating the header and
+    /// the buffer length.
+    ///
+    /// # Errors
+    /// Returns a [`WireError`] if the buffer is not a valid encoding of a `BitPackedArray<T>`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WireError>
+    where
+        T: WireType,
+    {
+        format::from_bytes(bytes)
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub fn encoding(&self) -> &Encoding<T> {
+        &self.encoding
+    }
+
+    /// The packed chunks, `1024 * width / T` elements each.
+    #[must_use]
+    pub fn packed(&self) -> &[T] {
+        &self.packed
+    }
+
+    fn header(&self, len: usize) -> SliceHeader {
+        SliceHeader {
+            width: self.width,
+            len,
+        }
+    }
+
+    fn packed_len(&self) -> usize {
+        128 * self.width / size_of::<T>()
+    }
+
+    fn chunk(&self, chunk: usize) -> &[T] {
+        let packed_len = self.packed_len();
+        &self.packed[chunk * packed_len..][..packed_len]
+    }
+
+    /// Returns the value at `index`.
+    ///
+    /// Bit-packed and frame-of-reference values are unpacked individually, whereas Delta values
+    /// require unpacking the preceding rows of their lane.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    #[must_use]
+    pub fn get(&self, index: usize) -> T {
+        assert!(
+            index < self.len,
+            "Index {index} out of bounds for length {}",
+            self.len
+        );
+        let (chunk, offset) = (index / 1024, index % 1024);
+
+        // SAFETY: `chunk` is exactly `1024 * width / T` elements and `offset < 1024`.
+        let unpack_single =
+            || unsafe { T::unchecked_unpack_single(self.width, self.chunk(chunk), offset) };
+        match &self.encoding {
+            Encoding::BitPacked => unpack_single(),
+            Encoding::FoR { references } => unpack_single().wrapping_add(&references[chunk]),
+            Encoding::Delta { bases } => {
+                // Delta chunks are packed transposed, see `crate::transpose`.
+                let transposed = untranspose(offset);
+                let bases = &bases[chunk * T::LANES..][..T::LANES];
+                // SAFETY: as above, and `bases` is exactly `T::LANES` elements.
+                unsafe {
+                    T::unchecked_undelta_single(self.width, self.chunk(chunk), bases, transposed)
+                }
+            }
+        }
+    }
+
+    /// Decodes all values, appending them to `output`.
+    pub fn decode_into(&self, output: &mut Vec<T>) {
+        output.reserve(self.len);
+        match &self.encoding {
+            Encoding::BitPacked => T::unpack_slice(self.header(self.len), &self.packed, output),
+            Encoding::Delta { bases } => {
+                T::undelta_pack_slice(self.header(self.len), &self.packed, bases, output);
+            }
+            Encoding::FoR { .. } => {
+                for chunk in 0..self.len.div_ceil(1024) {
+                    self.decode_chunk(chunk, output);
+                }
+            }
+        }
+    }
+
+    /// Decodes the values of a single chunk, appending them to `output`.
+    fn decode_chunk(&self, chunk: usize, output: &mut Vec<T>) {
+        let n = (self.len - chunk * 1024).min(1024);
+        let packed = self.chunk(chunk);
+        match &self.encoding {
+            Encoding::BitPacked => T::unpack_slice(self.header(n), packed, output),
+            Encoding::FoR { references } => {
+                T::unfor_pack_slice(self.header(n), packed, references[chunk], output);
+            }
+            Encoding::Delta { bases } => {
+                let bases = &bases[chunk * T::LANES..][..T::LANES];
+                T::undelta_pack_slice(self.header(n), packed, bases, output);
+            }
+        }
+    }
+
+    /// Iterates over the values, decoding one chunk at a time.
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            array: self,
+            chunk: 0,
+            buffer: Vec::with_capacity(1024),
+            position: 0,
+        }
+    }
+}
+
+impl<'a, T: Delta + FoR + WrappingAdd> IntoIterator for &'a BitPackedArray<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, T> {
+    array: &'a BitPackedArray<T>,
+    chunk: usize,
+    buffer: Vec<T>,
+    position: usize,
+}
+
+impl<T: Delta + FoR + WrappingAdd> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.position == self.buffer.len() {
+            if self.chunk * 1024 >= self.array.len {
+                return None;
+            }
+            self.buffer.clear();
+            self.array.decode_chunk(self.chunk, &mut self.buffer);
+            self.chunk += 1;
+            self.position = 0;
+        }
+        let value = self.buffer[self.position];
+        self.position += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.array.len - (self.chunk * 1024).min(self.array.len)
+            + self.buffer.len()
+            - self.position;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Delta + FoR + WrappingAdd> ExactSizeIterator for Iter<'_, T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check<T: Delta + FoR + WrappingAdd + core::fmt::Debug>(
+        array: &BitPackedArray<T>,
+        values: &[T],
+    ) {
+        assert_eq!(array.len(), values.len());
+        for (i, &value) in values.iter().enumerate() {
+            assert_eq!(array.get(i), value, "Mismatch at index {i}");
+        }
+
+        let mut decoded = Vec::new();
+        array.decode_into(&mut decoded);
+        assert_eq!(decoded, values);
+
+        assert_eq!(array.iter().len(), values.len());
+        assert!(array.iter().eq(values.iter().copied()));
+    }
+
+    #[test]
+    fn test_bitpacked_array() {
+        let values: Vec<u32> = (0..2500).map(|i| i % 1000).collect();
+        check(&BitPackedArray::pack(10, &values), &values);
+    }
+
+    #[test]
+    fn test_for_array() {
+        let values: Vec<u64> = (0..2500).map(|i| ((i / 1024) << 40) | (i % 1024)).collect();
+        let array = BitPackedArray::for_pack(10, &values);
+        assert_eq!(
+            array.encoding(),
+            &Encoding::FoR {
+                references: alloc::vec![0, 1 << 40, 2 << 40]
+            }
+        );
+        check(&array, &values);
+    }
+
+    #[test]
+    fn test_delta_array() {
+        let values: Vec<u16> = (0..3000).map(|i| i * 2).collect();
+        let array = BitPackedArray::delta_pack(2, &values);
+        check(&array, &values);
+        check(&BitPackedArray::delta_pack(2, &values[..0]), &[]);
+    }
+}
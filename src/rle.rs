@@ -0,0 +1,148 @@
+use crate::{iterate, unpack, BitPackWidth, BitPacking, FastLanes, SupportedBitPackWidth};
+use paste::paste;
+
+/// Run-length encoding over the transposed `FastLanes` layout.
+///
+/// A vector is split into its run values and, for every element, the `u16` index of the run it
+/// belongs to. Runs are detected along each `u16` lane, so the indices are non-decreasing within
+/// a lane and can be delta-encoded against `indices[..64]` (the first row of each lane) into
+/// mostly 0 or 1 bit deltas. Decoding then becomes a fused unpack + undelta + lookup.
+pub trait Rle: BitPacking {
+    /// Encodes a transposed vector into its run values and per-element run indices.
+    /// Returns the number of runs written to `values`.
+    fn rle(input: &[Self; 1024], values: &mut [Self; 1024], indices: &mut [u16; 1024]) -> usize;
+
+    /// Decodes a vector from its run values and per-element run indices, as written by `rle`.
+    fn unrle(values: &[Self], indices: &[u16; 1024], output: &mut [Self; 1024]);
+
+    /// Decodes `W`-bit packed run index deltas, as produced by `Delta::delta` over the `u16`
+    /// indices, directly into the run values.
+    fn unrle_pack<const W: usize>(
+        input: &[u16; 1024 * W / u16::T],
+        base: &[u16; u16::LANES],
+        values: &[Self],
+        output: &mut [Self; 1024],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<u16>;
+}
+
+macro_rules! impl_rle {
+    ($T:ty) => {
+        paste! {
+            impl Rle for $T {
+                fn rle(input: &[Self; 1024], values: &mut [Self; 1024], indices: &mut [u16; 1024]) -> usize {
+                    let mut runs = 0;
+                    for lane in 0..u16::LANES {
+                        iterate!(u16, lane, |$idx| {
+                            let next = input[$idx];
+                            if runs == 0 || values[runs - 1] != next {
+                                values[runs] = next;
+                                runs += 1;
+                            }
+                            indices[$idx] = (runs - 1) as u16;
+                        });
+                    }
+                    runs
+                }
+
+                fn unrle(values: &[Self], indices: &[u16; 1024], output: &mut [Self; 1024]) {
+                    for i in 0..1024 {
+                        output[i] = values[indices[i] as usize];
+                    }
+                }
+
+                #[inline(never)]
+                fn unrle_pack<const W: usize>(
+                    input: &[u16; 1024 * W / u16::T],
+                    base: &[u16; u16::LANES],
+                    values: &[Self],
+                    output: &mut [Self; 1024],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<u16>,
+                {
+                    #[allow(unused_assignments)]
+                    for lane in 0..u16::LANES {
+                        let mut prev = base[lane];
+                        unpack!(u16, W, input, lane, |$idx, $elem| {
+                            let next = $elem.wrapping_add(prev);
+                            output[$idx] = values[next as usize];
+                            prev = next;
+                        });
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_rle!(u8);
+impl_rle!(u16);
+impl_rle!(u32);
+impl_rle!(u64);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Delta, Transpose};
+    use core::fmt::Debug;
+
+    fn try_rle<T: Rle + Debug>() {
+        let mut values = [T::zero(); 1024];
+        for i in 0..1024 {
+            values[i] = T::from((i / 10) % 7).unwrap();
+        }
+
+        let mut transposed = [T::zero(); 1024];
+        Transpose::transpose(&values, &mut transposed);
+
+        let mut runs = [T::zero(); 1024];
+        let mut indices = [0u16; 1024];
+        let n = Rle::rle(&transposed, &mut runs, &mut indices);
+
+        // Scalar reference: each element maps back to its own value, and no two neighbouring
+        // runs share a value.
+        for i in 0..1024 {
+            assert_eq!(runs[indices[i] as usize], transposed[i]);
+        }
+        for i in 1..n {
+            assert_ne!(runs[i - 1], runs[i]);
+        }
+
+        let mut unrle = [T::zero(); 1024];
+        Rle::unrle(&runs[..n], &indices, &mut unrle);
+        assert_eq!(transposed, unrle);
+
+        // Run indices only ever step by 0 or 1 within a lane.
+        let base: [u16; 64] = *arrayref::array_ref![indices, 0, 64];
+        let mut deltas = [0u16; 1024];
+        Delta::delta(&indices, &base, &mut deltas);
+        assert!(deltas.iter().all(|&d| d <= 1));
+
+        let mut packed = [0u16; 64];
+        BitPacking::pack::<1>(&deltas, &mut packed);
+
+        let mut unpacked = [T::zero(); 1024];
+        Rle::unrle_pack::<1>(&packed, &base, &runs[..n], &mut unpacked);
+        assert_eq!(transposed, unpacked);
+    }
+
+    #[test]
+    fn test_rle() {
+        try_rle::<u8>();
+        try_rle::<u16>();
+        try_rle::<u32>();
+        try_rle::<u64>();
+    }
+
+    #[test]
+    fn test_rle_constant() {
+        let values = [42u32; 1024];
+        let mut runs = [0; 1024];
+        let mut indices = [0u16; 1024];
+        assert_eq!(Rle::rle(&values, &mut runs, &mut indices), 1);
+
+        let mut unpacked = [0; 1024];
+        Rle::unrle_pack::<0>(&[], &[0; 64], &runs[..1], &mut unpacked);
+        assert_eq!(values, unpacked);
+    }
+}
@@ -1,12 +1,12 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
 use crate::{FastLanes, FL_ORDER};
 use seq_macro::seq;
 
-pub trait Transpose: FastLanes {
-    fn transpose(input: &[Self; 1024], output: &mut [Self; 1024]);
-    fn untranspose(input: &[Self; 1024], output: &mut [Self; 1024]);
-}
-
-impl<T: FastLanes> Transpose for T {
+/// Transposition is a pure permutation of the 1024 elements. It is implemented for all
+/// `FastLanes` types and for their signed counterparts, which the signed entry points transpose.
+pub trait Transpose: Sized + Copy {
     #[inline(never)]
     fn transpose(input: &[Self; 1024], output: &mut [Self; 1024]) {
         seq!(i in 0..1024 {
@@ -20,8 +20,47 @@ impl<T: FastLanes> Transpose for T {
             output[transpose(i)] = input[i];
         });
     }
+
+    /// Transposes a slice of any length in chunks of 1024 elements, appending to `output`.
+    /// The final partial chunk is padded by repeating its last element.
+    #[cfg(feature = "alloc")]
+    fn transpose_slice(input: &[Self], output: &mut Vec<Self>) {
+        output.reserve(input.len().div_ceil(1024) * 1024);
+        for chunk in input.chunks(1024) {
+            let mut padded = [chunk[chunk.len() - 1]; 1024];
+            padded[..chunk.len()].copy_from_slice(chunk);
+            let mut transposed = padded;
+            Self::transpose(&padded, &mut transposed);
+            output.extend_from_slice(&transposed);
+        }
+    }
+
+    /// Untransposes the first `len` elements of a slice produced by `transpose_slice`,
+    /// appending them to `output`.
+    #[cfg(feature = "alloc")]
+    fn untranspose_slice(input: &[Self], len: usize, output: &mut Vec<Self>) {
+        assert_eq!(
+            input.len(),
+            len.div_ceil(1024) * 1024,
+            "Input must hold whole chunks"
+        );
+        output.reserve(len);
+        for (i, chunk) in input.chunks_exact(1024).enumerate() {
+            let chunk = arrayref::array_ref![chunk, 0, 1024];
+            let mut untransposed = *chunk;
+            Self::untranspose(chunk, &mut untransposed);
+            output.extend_from_slice(&untransposed[..(len - i * 1024).min(1024)]);
+        }
+    }
 }
 
+impl<T: FastLanes> Transpose for T {}
+
+impl Transpose for i8 {}
+impl Transpose for i16 {}
+impl Transpose for i32 {}
+impl Transpose for i64 {}
+
 /// Return the corresponding index in a transposed `FastLanes` vector.
 #[allow(clippy::inline_always)]
 #[inline(always)]
@@ -34,3 +73,64 @@ pub const fn transpose(idx: usize) -> usize {
 
     (lane * 64) + (FL_ORDER[order] * 8) + row
 }
+
+/// Return the index in a transposed `FastLanes` vector that holds the element at `idx` of the
+/// untransposed vector, i.e. the inverse of [`transpose`].
+#[allow(clippy::inline_always)]
+#[inline(always)]
+#[must_use]
+pub const fn untranspose(idx: usize) -> usize {
+    let row = idx % 8;
+    let order = (idx / 8) % 8;
+    let lane = idx / 64;
+
+    (row * 128) + (FL_ORDER[order] * 16) + lane
+}
+
+/// Return the index of the element at `row` of `lane` in the order the `FastLanes` kernels visit
+/// a vector.
+#[allow(clippy::inline_always)]
+#[inline(always)]
+#[must_use]
+pub const fn fl_index(row: usize, lane: usize) -> usize {
+    (FL_ORDER[row / 8] * 16) + ((row % 8) * 128) + lane
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_transpose_slice() {
+        use super::*;
+
+        let values: Vec<u8> = (0..2049usize).map(|i| (i % 251) as u8).collect();
+
+        let mut transposed = Vec::new();
+        Transpose::transpose_slice(&values, &mut transposed);
+        assert_eq!(transposed.len(), 3072);
+
+        let mut untransposed = Vec::new();
+        Transpose::untranspose_slice(&transposed, values.len(), &mut untransposed);
+        assert_eq!(values, untransposed);
+    }
+
+    #[test]
+    fn test_fl_index() {
+        use super::*;
+
+        // `u64` vectors have 16 lanes of 64 rows, so the transposed order is the kernel order.
+        for (row, lane) in (0..64).flat_map(|row| (0..16).map(move |lane| (row, lane))) {
+            assert_eq!(transpose(fl_index(row, lane)), lane * 64 + row);
+        }
+    }
+
+    #[test]
+    fn test_untranspose() {
+        use super::*;
+
+        for i in 0..1024 {
+            assert_eq!(transpose(untranspose(i)), i);
+            assert_eq!(untranspose(transpose(i)), i);
+        }
+    }
+}
@@ -0,0 +1,19 @@
+/// Branch-free compaction over the 1024 indices of a vector. `write(n, i)` writes the candidate
+/// for index `i` to position `n` and returns whether to keep it. Every candidate is written, and
+/// `n` only advances past kept ones. Returns the number of kept candidates.
+pub(crate) fn compact_with(mut write: impl FnMut(usize, usize) -> bool) -> usize {
+    let mut n = 0;
+    for i in 0..1024 {
+        n += usize::from(write(n, i));
+    }
+    n
+}
+
+/// The number of elements sampled to choose the parameters of a vector, see [`sample_index`].
+pub(crate) const SAMPLES: usize = 32;
+
+/// The index of the `i`th of [`SAMPLES`] sampled elements. A stride of 33 also samples every
+/// residue modulo 32, which avoids aliasing with periodic data.
+pub(crate) const fn sample_index(i: usize) -> usize {
+    i * (1024 / SAMPLES + 1)
+}
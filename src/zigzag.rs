@@ -0,0 +1,133 @@
+#![allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+
+use crate::{pack, unpack, BitPackWidth, BitPacking, FastLanes, SupportedBitPackWidth};
+use num_traits::{PrimInt, Signed};
+use paste::paste;
+
+/// `ZigZag` maps signed integers onto unsigned integers such that values of small magnitude,
+/// whether positive or negative, have small unsigned encodings (0, -1, 1, -2, ... => 0, 1, 2, 3, ...).
+pub trait ZigZag: BitPacking {
+    type Signed: PrimInt + Signed;
+
+    fn zigzag(input: &[Self::Signed; 1024], output: &mut [Self; 1024]);
+
+    fn unzigzag(input: &[Self; 1024], output: &mut [Self::Signed; 1024]);
+
+    fn zigzag_pack<const W: usize>(
+        input: &[Self::Signed; 1024],
+        output: &mut [Self; 1024 * W / Self::T],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+
+    fn unzigzag_pack<const W: usize>(
+        input: &[Self; 1024 * W / Self::T],
+        output: &mut [Self::Signed; 1024],
+    ) where
+        BitPackWidth<W>: SupportedBitPackWidth<Self>;
+}
+
+macro_rules! impl_zigzag {
+    ($T:ty, $S:ty) => {
+        paste! {
+            impl ZigZag for $T {
+                type Signed = $S;
+
+                fn zigzag(input: &[$S; 1024], output: &mut [Self; 1024]) {
+                    for i in 0..1024 {
+                        output[i] = ((input[i] << 1) ^ (input[i] >> (Self::T - 1))) as $T;
+                    }
+                }
+
+                fn unzigzag(input: &[Self; 1024], output: &mut [$S; 1024]) {
+                    for i in 0..1024 {
+                        output[i] = ((input[i] >> 1) as $S) ^ -((input[i] & 1) as $S);
+                    }
+                }
+
+                fn zigzag_pack<const W: usize>(
+                    input: &[$S; 1024],
+                    output: &mut [Self; 1024 * W / Self::T],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    for lane in 0..Self::LANES {
+                        pack!($T, W, output, lane, |$idx| {
+                            ((input[$idx] << 1) ^ (input[$idx] >> (Self::T - 1))) as $T
+                        });
+                    }
+                }
+
+                fn unzigzag_pack<const W: usize>(
+                    input: &[Self; 1024 * W / Self::T],
+                    output: &mut [$S; 1024],
+                ) where
+                    BitPackWidth<W>: SupportedBitPackWidth<Self>,
+                {
+                    for lane in 0..Self::LANES {
+                        unpack!($T, W, input, lane, |$idx, $elem| {
+                            output[$idx] = (($elem >> 1) as $S) ^ -(($elem & 1) as $S)
+                        });
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_zigzag!(u8, i8);
+impl_zigzag!(u16, i16);
+impl_zigzag!(u32, i32);
+impl_zigzag!(u64, i64);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::mem::size_of;
+
+    #[test]
+    fn test_zigzag() {
+        let mut values: [i32; 1024] = [0; 1024];
+        for i in 0..1024 {
+            values[i] = i as i32 - 512;
+        }
+        values[0] = i32::MIN;
+        values[1] = i32::MAX;
+
+        let mut encoded = [0u32; 1024];
+        ZigZag::zigzag(&values, &mut encoded);
+        assert_eq!(encoded[511], 1); // -1
+        assert_eq!(encoded[512], 0); // 0
+        assert_eq!(encoded[513], 2); // 1
+        assert_eq!(encoded[0], u32::MAX);
+        assert_eq!(encoded[1], u32::MAX - 1);
+
+        let mut decoded = [0i32; 1024];
+        ZigZag::unzigzag(&encoded, &mut decoded);
+        assert_eq!(values, decoded);
+    }
+
+    #[test]
+    fn test_zigzag_pack() {
+        const W: usize = 11;
+        let mut values: [i16; 1024] = [0; 1024];
+        for i in 0..1024 {
+            values[i] = i as i16 - 1000;
+        }
+
+        let mut packed = [0; 128 * W / size_of::<u16>()];
+        u16::zigzag_pack::<W>(&values, &mut packed);
+
+        let mut unpacked = [0i16; 1024];
+        u16::unzigzag_pack::<W>(&packed, &mut unpacked);
+        assert_eq!(values, unpacked);
+
+        // The signed BitPacking entry points produce the same layout.
+        let mut packed_signed = [0; 128 * W / size_of::<u16>()];
+        u16::pack_signed::<W>(&values, &mut packed_signed);
+        assert_eq!(packed, packed_signed);
+
+        let mut unpacked_signed = [0i16; 1024];
+        u16::unpack_signed::<W>(&packed_signed, &mut unpacked_signed);
+        assert_eq!(values, unpacked_signed);
+    }
+}
@@ -0,0 +1,67 @@
+// Generates the golden vectors used to test `RowMajorBitPacking`, which is meant to match the
+// row-major addressing of the original C++ FastLanes bit-packing kernels
+// (https://github.com/cwida/FastLanes).
+//
+// This is a deliberately naive, standalone transcription of the addressing used by the reference
+// scalar kernels, e.g. `unpack_3bw_32ow_32crw_1uf`: value `i` of a 1024-value vector belongs to
+// lane `i % LANES` at row `i / LANES`, and the rows of each lane are packed LSB-first into that
+// lane's words, which are strided by `LANES` (`in[LANES * word + lane]`). It shares no code with
+// the Rust implementation.
+//
+// The vectors are NOT produced by the upstream kernels, so they only catch divergence between this
+// transcription and the Rust code, and say nothing about byte equality with upstream.
+//
+// Upstream revision: none. To prove byte equality, `generate` has to be replaced by calls to the
+// upstream scalar pack kernels for each (T, W) below, writing their output words in the same
+// little-endian format, and the upstream commit used recorded here.
+//
+// Usage: cc -O2 -o generate generate.c && ./generate
+#include <stdint.h>
+#include <stdio.h>
+
+static uint64_t value(int i, int w) {
+    uint64_t x = (uint64_t)i * 0x9E3779B97F4A7C15ull;
+    return (x >> 32) & ((1ull << w) - 1);
+}
+
+static void generate(int t, int w) {
+    int lanes = 1024 / t;
+    uint64_t words[1024] = {0};
+    uint64_t t_mask = t == 64 ? ~0ull : (1ull << t) - 1;
+
+    for (int lane = 0; lane < lanes; lane++) {
+        uint64_t acc = 0;
+        int bits = 0;
+        int word = 0;
+        for (int row = 0; row < t; row++) {
+            uint64_t v = value(row * lanes + lane, w);
+            acc |= v << bits;
+            bits += w;
+            if (bits >= t) {
+                words[word * lanes + lane] = acc & t_mask;
+                word++;
+                bits -= t;
+                acc = bits ? v >> (w - bits) : 0;
+            }
+        }
+    }
+
+    char path[32];
+    snprintf(path, sizeof(path), "u%d_w%d.bin", t, w);
+    FILE *f = fopen(path, "wb");
+    for (int i = 0; i < 1024 * w / t; i++) {
+        for (int b = 0; b < t / 8; b++) {
+            fputc((int)((words[i] >> (8 * b)) & 0xff), f);
+        }
+    }
+    fclose(f);
+}
+
+int main(void) {
+    generate(8, 3);
+    generate(16, 7);
+    generate(32, 3);
+    generate(32, 17);
+    generate(64, 13);
+    return 0;
+}